@@ -0,0 +1,56 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use redstone_simulator::world::flat::FlatWorld;
+use redstone_simulator::world::World;
+use std::fs::File;
+
+fn stress(c: &mut Criterion) {
+    for name in ["stress_repeater", "stress_comparator"] {
+        let file = File::open(format!("./schematics/{name}.schem")).unwrap();
+        let mut world = World::from(file);
+        world.step_with_trigger();
+        let mut flat = FlatWorld::new(&world).unwrap();
+
+        let mut group = c.benchmark_group(name);
+        group.bench_function("world", |b| {
+            b.iter(|| {
+                black_box(&mut world).step();
+            })
+        });
+        group.bench_function("flat", |b| {
+            b.iter(|| {
+                black_box(&mut flat).step();
+            })
+        });
+        group.finish();
+    }
+}
+
+fn cpu_fib(c: &mut Criterion) {
+    let file = File::open("./schematics/cpu_fib.schem").unwrap();
+    let mut world = World::from(file);
+    let mut flat = FlatWorld::new(&world).unwrap();
+
+    let mut group = c.benchmark_group("cpu_fib");
+    group.bench_function("world", |b| {
+        b.iter(|| {
+            black_box(&mut world).step_with_trigger();
+            black_box(&mut world).step_with_trigger();
+            for _ in 0..40 {
+                black_box(&mut world).step();
+            }
+        })
+    });
+    group.bench_function("flat", |b| {
+        b.iter(|| {
+            black_box(&mut flat).step_with_trigger();
+            black_box(&mut flat).step_with_trigger();
+            for _ in 0..40 {
+                black_box(&mut flat).step();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(flat, stress, cpu_fib);
+criterion_main!(flat);
@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use redstone_simulator::world::parallel::ParallelWorld;
+use redstone_simulator::world::World;
+use std::fs::File;
+
+const THREADS: [usize; 2] = [1, 4];
+
+fn stress(c: &mut Criterion) {
+    for name in ["stress_srepeater", "stress_repeater", "stress_comparator"] {
+        for threads in THREADS {
+            let file = File::open(format!("./schematics/{name}.schem")).unwrap();
+            let mut world = World::from(file);
+            world.step_with_trigger();
+            let mut parallel = ParallelWorld::new(&world, threads).unwrap();
+            c.bench_function(&format!("parallel_{name}_{threads}"), |b| {
+                b.iter(|| {
+                    black_box(&mut parallel).step();
+                })
+            });
+        }
+    }
+}
+
+fn cpu_fib(c: &mut Criterion) {
+    for threads in THREADS {
+        let file = File::open("./schematics/cpu_fib.schem").unwrap();
+        let mut parallel = ParallelWorld::new(&World::from(file), threads).unwrap();
+        c.bench_function(&format!("parallel_cpu_fib_{threads}"), |b| {
+            b.iter(|| {
+                black_box(&mut parallel).step_with_trigger();
+                black_box(&mut parallel).step_with_trigger();
+                for _ in 0..40 {
+                    black_box(&mut parallel).step();
+                }
+            })
+        });
+    }
+}
+
+criterion_group!(parallel, stress, cpu_fib);
+criterion_main!(parallel);
@@ -1,4 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use redstone_simulator::world::lanes::LaneWorld;
 use redstone_simulator::world::World;
 use std::fs::File;
 
@@ -13,6 +14,18 @@ fn stress_srepeater(c: &mut Criterion) {
     });
 }
 
+fn stress_srepeater_lanes(c: &mut Criterion) {
+    let file = File::open("./schematics/stress_srepeater.schem").unwrap();
+    let mut world = World::from(file);
+    world.step_with_trigger();
+    let mut lanes = LaneWorld::new(&world).unwrap();
+    c.bench_function("stress_srepeater_lanes", |b| {
+        b.iter(|| {
+            black_box(&mut lanes).step();
+        })
+    });
+}
+
 fn stress_repeater(c: &mut Criterion) {
     let file = File::open("./schematics/stress_repeater.schem").unwrap();
     let mut world = World::from(file);
@@ -35,5 +48,11 @@ fn stress_comparator(c: &mut Criterion) {
     });
 }
 
-criterion_group!(stress, stress_srepeater, stress_repeater, stress_comparator);
+criterion_group!(
+    stress,
+    stress_srepeater,
+    stress_srepeater_lanes,
+    stress_repeater,
+    stress_comparator
+);
 criterion_main!(stress);
@@ -0,0 +1,84 @@
+// Behavioural models of the blocks in a netlist exported by redstone-simulator.
+//
+// Every rising edge of `clk` is one redstone tick. Signals are 4-bit signal strengths, except for
+// the inputs of repeaters and simple repeaters, which only see whether any input is powered.
+
+// Repeater with a delay of DELAY ticks, which keeps its state while `lock` is high. `lock_next` is
+// high if the repeater is locked by the end of the tick, in which case it keeps its output.
+// `out_next` is whether it is powered after the tick, for the repeaters it locks.
+module rs_repeater #(
+    parameter DELAY = 1,
+    parameter INIT = 0
+) (
+    input wire clk,
+    input wire in,
+    input wire lock,
+    input wire lock_next,
+    output wire [3:0] out,
+    output wire out_next
+);
+    reg powered = INIT;
+    reg next = INIT;
+
+    // Ticks since the output started to change.
+    reg [1:0] count = 0;
+
+    // A rising input restarts the count, a falling input only turns off a stable output.
+    wire rising = in && !next;
+    wire falling = !in && next && count == 0;
+    wire next_new = rising || (next && !falling);
+    wire [1:0] count_new = rising ? 2'd0 : count;
+    wire counting = powered != next_new;
+    wire fire = counting && count_new == DELAY - 1;
+
+    assign out = powered ? 4'd15 : 4'd0;
+    assign out_next = !lock && fire && !lock_next ? next_new : powered;
+
+    always @(posedge clk) begin
+        if (!lock) begin
+            next <= next_new;
+            if (fire) begin
+                count <= 0;
+                if (!lock_next) powered <= next_new;
+            end else if (counting) begin
+                count <= count_new + 1;
+            end else begin
+                count <= count_new;
+            end
+        end
+    end
+endmodule
+
+// Repeater with a delay of one tick, or a torch if INVERT is set. Redstone blocks are torches
+// without inputs.
+module rs_srepeater #(
+    parameter INVERT = 0,
+    parameter INIT = 0
+) (
+    input wire clk,
+    input wire in,
+    output wire [3:0] out
+);
+    reg powered = INIT;
+
+    assign out = powered ? 4'd15 : 4'd0;
+
+    always @(posedge clk) powered <= in ^ INVERT;
+endmodule
+
+// Comparator, which subtracts its side input from its rear input if SUBTRACT is set, and otherwise
+// outputs its rear input unless the side input is stronger.
+module rs_comparator #(
+    parameter SUBTRACT = 0,
+    parameter INIT = 0
+) (
+    input wire clk,
+    input wire [3:0] rear,
+    input wire [3:0] side,
+    output reg [3:0] out = INIT
+);
+    always @(posedge clk) begin
+        if (SUBTRACT) out <= rear > side ? rear - side : 4'd0;
+        else out <= side <= rear ? rear : 4'd0;
+    end
+endmodule
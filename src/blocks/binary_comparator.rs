@@ -0,0 +1,144 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::{Block, BlockState, OutputPower, RuntimeState, ToBlock, Updatable};
+use crate::world::graph::GNode;
+use crate::world::UpdatableList;
+use std::cell::Cell;
+
+/// Comparator whose inputs are either off or have a fixed signal strength, so it either outputs
+/// the signal strength of its rear inputs or nothing.
+#[derive(Copy, Clone, Debug)]
+pub struct CBinaryComparator {
+    signal: u8,
+
+    /// Signal strength of every rear input, and of the output while it is on.
+    on: u8,
+
+    mode: ComparatorMode,
+}
+
+impl CBinaryComparator {
+    pub fn new(signal: u8, on: u8, mode: ComparatorMode) -> CBinaryComparator {
+        CBinaryComparator { signal, on, mode }
+    }
+
+    pub fn on(&self) -> u8 {
+        self.on
+    }
+
+    pub fn mode(&self) -> ComparatorMode {
+        self.mode
+    }
+}
+
+#[derive(Debug)]
+pub struct BinaryComparator {
+    signal: Cell<u8>,
+
+    /// Signal of the comparator during the next tick.
+    next_signal: Cell<u8>,
+
+    on: u8,
+
+    mode: ComparatorMode,
+}
+
+impl BinaryComparator {
+    pub fn mode(&self) -> ComparatorMode {
+        self.mode
+    }
+}
+
+impl OutputPower for BinaryComparator {
+    fn output_power(&self) -> u8 {
+        self.signal.get()
+    }
+}
+
+impl OutputPower for CBinaryComparator {
+    fn output_power(&self) -> u8 {
+        self.signal
+    }
+}
+
+impl ToBlock for CBinaryComparator {
+    fn to_block(&self, _on_inputs: u8) -> Block {
+        Block::BinaryComparator(BinaryComparator {
+            signal: Cell::new(self.signal),
+            next_signal: Cell::new(self.signal),
+            on: self.on,
+            mode: self.mode,
+        })
+    }
+}
+
+impl Updatable for BinaryComparator {
+    #[inline(always)]
+    fn update(
+        &self,
+        idx: &'static GNode<Block, u8>,
+        _tick_updatable: &mut UpdatableList,
+        _up: bool,
+    ) -> bool {
+        let rear = idx
+            .incoming_rear
+            .iter()
+            .any(|e| e.node.weight.output_power() > e.weight);
+        let side = idx
+            .incoming_side
+            .iter()
+            .map(|e| e.node.weight.output_power().saturating_sub(e.weight))
+            .max()
+            .unwrap_or(0);
+
+        // Side inputs either turn the output off or do not affect it at all.
+        let off = match self.mode {
+            ComparatorMode::Compare => side > self.on,
+            ComparatorMode::Subtract => side >= self.on,
+        };
+        self.next_signal.set(if rear && !off { self.on } else { 0 });
+
+        self.signal.get() != self.next_signal.get()
+    }
+
+    fn late_update(
+        &self,
+        _idx: &'static GNode<Block, u8>,
+        _tick_updatable: &mut UpdatableList,
+        _tick_counter: usize,
+    ) -> Option<(u8, u8)> {
+        let old = self.signal.get();
+        self.signal.set(self.next_signal.get());
+
+        Some((old, self.signal.get()))
+    }
+}
+
+impl RuntimeState for BinaryComparator {
+    /// Has the state of a `Comparator`, which behaves the same given the same inputs.
+    fn state(&self) -> BlockState {
+        BlockState::Comparator {
+            signal: self.signal.get(),
+            next_signal: self.next_signal.get(),
+        }
+    }
+
+    fn set_state(&self, state: &BlockState) -> bool {
+        let BlockState::Comparator {
+            signal,
+            next_signal,
+        } = *state
+        else {
+            return false;
+        };
+        // A state of a comparator that is not binary.
+        if [signal, next_signal]
+            .iter()
+            .any(|&s| s != 0 && s != self.on)
+        {
+            return false;
+        }
+        self.signal.set(signal);
+        self.next_signal.set(next_signal);
+        true
+    }
+}
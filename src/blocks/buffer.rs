@@ -0,0 +1,170 @@
+use crate::blocks::{Block, BlockState, OutputPower, RuntimeState, ToBlock, Updatable};
+use crate::world::graph::GNode;
+use crate::world::UpdatableList;
+use std::cell::Cell;
+
+/// Longest chain of torches and simple repeaters a single buffer can stand for.
+pub const MAX_DELAY: usize = u32::BITS as usize;
+
+/// Chain of torches and simple repeaters, where every block only powers the next one.
+#[derive(Clone, Debug)]
+pub struct CBuffer {
+    delay: u8,
+
+    /// Whether the chain contains an odd number of torches.
+    inverted: bool,
+
+    /// Input of the chain during the last `delay` ticks, the most recent in the lowest bit.
+    history: u32,
+}
+
+impl CBuffer {
+    /// Creates a buffer from whether every block of the chain is powered and whether it inverts
+    /// its input, from the input to the output.
+    pub fn new(stages: &[(bool, bool)]) -> CBuffer {
+        assert!(!stages.is_empty() && stages.len() <= MAX_DELAY);
+
+        // Every block outputs the input of the chain of one tick before its predecessor.
+        let mut inverted = false;
+        let mut history = 0;
+        for (i, &(powered, inverts)) in stages.iter().enumerate() {
+            inverted ^= inverts;
+            history |= ((powered ^ inverted) as u32) << i;
+        }
+
+        CBuffer {
+            delay: stages.len() as u8,
+            inverted,
+            history,
+        }
+    }
+
+    pub fn delay(&self) -> u8 {
+        self.delay
+    }
+
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Whether the buffer outputs power now or at some point without any input.
+    pub fn is_powered(&self) -> bool {
+        self.inverted || self.history != 0
+    }
+}
+
+/// Chain of torches and simple repeaters simulated as a single node, which outputs its input of
+/// `delay` ticks ago.
+#[derive(Debug)]
+pub struct Buffer {
+    delay: u8,
+    inverted: bool,
+    history: Cell<u32>,
+
+    /// Input of the buffer during the current tick.
+    next: Cell<bool>,
+
+    last_update: Cell<usize>,
+}
+
+fn mask(delay: u8) -> u32 {
+    u32::MAX >> (u32::BITS - delay as u32)
+}
+
+impl Buffer {
+    fn shifted(&self, input: bool) -> u32 {
+        ((self.history.get() << 1) | input as u32) & mask(self.delay)
+    }
+}
+
+impl ToBlock for CBuffer {
+    fn to_block(&self, _on_inputs: u8) -> Block {
+        Block::Buffer(Buffer {
+            delay: self.delay,
+            inverted: self.inverted,
+            history: Cell::new(self.history),
+            next: Cell::new(false),
+            last_update: Cell::new(usize::MAX),
+        })
+    }
+}
+
+/// Output power of a buffer with the given history.
+fn output_power(history: u32, delay: u8, inverted: bool) -> u8 {
+    if ((history >> (delay - 1)) & 1 == 1) != inverted {
+        15
+    } else {
+        0
+    }
+}
+
+impl OutputPower for Buffer {
+    fn output_power(&self) -> u8 {
+        output_power(self.history.get(), self.delay, self.inverted)
+    }
+}
+
+impl OutputPower for CBuffer {
+    fn output_power(&self) -> u8 {
+        output_power(self.history, self.delay, self.inverted)
+    }
+}
+
+impl Updatable for Buffer {
+    #[inline(always)]
+    fn update(
+        &self,
+        idx: &'static GNode<Block, u8>,
+        _tick_updatable: &mut UpdatableList,
+        _up: bool,
+    ) -> bool {
+        let s_new = idx
+            .incoming_rear
+            .iter()
+            .any(|e| e.node.weight.output_power().saturating_sub(e.weight) > 0);
+        self.next.set(s_new);
+
+        self.shifted(s_new) != self.history.get()
+    }
+
+    fn late_update(
+        &self,
+        idx: &'static GNode<Block, u8>,
+        tick_updatable: &mut UpdatableList,
+        tick_counter: usize,
+    ) -> Option<(u8, u8)> {
+        if tick_counter == self.last_update.get() {
+            return None;
+        }
+        self.last_update.set(tick_counter);
+
+        let old = self.output_power();
+        self.history.set(self.shifted(self.next.get()));
+        tick_updatable.push(idx);
+
+        let new = self.output_power();
+        (old != new).then_some((old, new))
+    }
+}
+
+impl RuntimeState for Buffer {
+    fn state(&self) -> BlockState {
+        BlockState::Buffer {
+            history: self.history.get(),
+            last_update: self.last_update.get(),
+        }
+    }
+
+    fn set_state(&self, state: &BlockState) -> bool {
+        let BlockState::Buffer {
+            history,
+            last_update,
+        } = *state
+        else {
+            return false;
+        };
+        self.history.set(history);
+        self.last_update.set(last_update);
+        true
+    }
+}
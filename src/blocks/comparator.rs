@@ -1,5 +1,7 @@
 use crate::blocks::facing::Facing;
-use crate::blocks::{Block, BlockConnections, InputSide, OutputPower, ToBlock, Updatable};
+use crate::blocks::{
+    Block, BlockConnections, BlockState, InputSide, OutputPower, RuntimeState, ToBlock, Updatable,
+};
 use crate::world::data::TileMap;
 use crate::world::graph::GNode;
 use crate::world::UpdatableList;
@@ -22,10 +24,28 @@ pub struct Comparator {
     mode: ComparatorMode,
 }
 
+impl Comparator {
+    pub fn mode(&self) -> ComparatorMode {
+        self.mode
+    }
+
+    pub fn entity_power(&self) -> Option<u8> {
+        self.entity_power
+    }
+}
+
 impl CComparator {
     pub fn contains_entity_power(&self) -> bool {
         self.entity_power.is_some()
     }
+
+    pub fn entity_power(&self) -> Option<u8> {
+        self.entity_power
+    }
+
+    pub fn mode(&self) -> ComparatorMode {
+        self.mode
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -42,7 +62,7 @@ pub struct CComparator {
     entity_power: Option<u8>,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ComparatorMode {
     Compare,
     Subtract,
@@ -140,6 +160,28 @@ impl Updatable for Comparator {
     }
 }
 
+impl RuntimeState for Comparator {
+    fn state(&self) -> BlockState {
+        BlockState::Comparator {
+            signal: self.signal.get(),
+            next_signal: self.next_signal.get(),
+        }
+    }
+
+    fn set_state(&self, state: &BlockState) -> bool {
+        let BlockState::Comparator {
+            signal,
+            next_signal,
+        } = *state
+        else {
+            return false;
+        };
+        self.signal.set(signal);
+        self.next_signal.set(next_signal);
+        true
+    }
+}
+
 impl From<HashMap<&str, &str>> for CComparator {
     fn from(meta: HashMap<&str, &str>) -> Self {
         CComparator {
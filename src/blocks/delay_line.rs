@@ -0,0 +1,246 @@
+use crate::blocks::repeater::CRepeater;
+use crate::blocks::{Block, BlockState, OutputPower, RuntimeState, ToBlock, Updatable};
+use crate::world::graph::GNode;
+use crate::world::UpdatableList;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
+
+/// Chain of repeaters that cannot be locked, where every repeater only powers the next one.
+#[derive(Clone, Debug)]
+pub struct CDelayLine {
+    /// Delay and whether it is powered of every repeater, from the input to the output.
+    stages: Vec<(u8, bool)>,
+}
+
+impl CDelayLine {
+    pub fn new(repeaters: &[CRepeater]) -> CDelayLine {
+        CDelayLine {
+            stages: repeaters
+                .iter()
+                .map(|r| (r.delay(), r.is_powered()))
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Total delay of the repeaters in the chain.
+    pub fn delay(&self) -> usize {
+        self.stages.iter().map(|&(delay, _)| delay as usize).sum()
+    }
+
+    /// Whether any repeater in the chain is powered.
+    pub fn is_powered(&self) -> bool {
+        self.stages.iter().any(|&(_, powered)| powered)
+    }
+}
+
+/// Runtime state of a single repeater of a `DelayLine`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Stage {
+    pub delay: u8,
+    pub powered: bool,
+    pub next_powered: bool,
+
+    /// Tick at the end of which `powered` becomes `next_powered`, once the change is scheduled.
+    pub flip: Option<usize>,
+}
+
+/// Chain of repeaters simulated as a single node, which behaves exactly like the repeaters would,
+/// including the extension of pulses shorter than a delay and the shortening of gaps between
+/// pulses.
+///
+/// Instead of counting down every repeater on every tick, the output changes of the repeaters are
+/// kept in a queue ordered by the tick they happen at, and only the repeaters whose input or output
+/// changed are updated.
+#[derive(Debug)]
+pub struct DelayLine {
+    stages: Box<[Cell<Stage>]>,
+
+    /// Scheduled output changes, as the tick at the end of which they happen and the stage.
+    pulses: RefCell<BTreeSet<(usize, usize)>>,
+
+    /// Stages that started to change their output during the current tick.
+    started: RefCell<Vec<usize>>,
+
+    /// Stages whose input or output changed at the end of the previous tick.
+    due: RefCell<Vec<usize>>,
+
+    last_update: Cell<usize>,
+}
+
+impl ToBlock for CDelayLine {
+    fn to_block(&self, _on_inputs: u8) -> Block {
+        Block::DelayLine(DelayLine {
+            stages: self
+                .stages
+                .iter()
+                .map(|&(delay, powered)| {
+                    Cell::new(Stage {
+                        delay,
+                        powered,
+                        next_powered: powered,
+                        flip: None,
+                    })
+                })
+                .collect(),
+            pulses: RefCell::new(BTreeSet::new()),
+            started: RefCell::new(vec![]),
+            // The first update checks every stage against its input.
+            due: RefCell::new((0..self.stages.len()).collect()),
+            last_update: Cell::new(usize::MAX),
+        })
+    }
+}
+
+impl DelayLine {
+    /// Updates stage `i` like `Repeater::update` without side inputs.
+    fn update_stage(&self, i: usize, s_new: bool) {
+        let mut stage = self.stages[i].get();
+        if s_new && !stage.next_powered {
+            stage.next_powered = true;
+            if stage.powered {
+                // The input came back before the output turned off, so it never does.
+                if let Some(flip) = stage.flip.take() {
+                    self.pulses.borrow_mut().remove(&(flip, i));
+                }
+            } else {
+                self.started.borrow_mut().push(i);
+            }
+        } else if !s_new && stage.next_powered && stage.powered {
+            // A rising output is never cut short, only a stable one turns off.
+            stage.next_powered = false;
+            self.started.borrow_mut().push(i);
+        }
+        self.stages[i].set(stage);
+    }
+}
+
+impl OutputPower for DelayLine {
+    fn output_power(&self) -> u8 {
+        if self.stages[self.stages.len() - 1].get().powered {
+            15
+        } else {
+            0
+        }
+    }
+}
+
+impl OutputPower for CDelayLine {
+    fn output_power(&self) -> u8 {
+        if self.stages[self.stages.len() - 1].1 {
+            15
+        } else {
+            0
+        }
+    }
+}
+
+impl Updatable for DelayLine {
+    #[inline(always)]
+    fn update(
+        &self,
+        idx: &'static GNode<Block, u8>,
+        _tick_updatable: &mut UpdatableList,
+        _up: bool,
+    ) -> bool {
+        let s_new = idx
+            .incoming_rear
+            .iter()
+            .any(|e| e.node.weight.output_power().saturating_sub(e.weight) > 0);
+        self.update_stage(0, s_new);
+
+        // Every repeater sees the output the previous one had at the start of the tick.
+        for i in self.due.take() {
+            if i > 0 {
+                self.update_stage(i, self.stages[i - 1].get().powered);
+            }
+        }
+
+        !self.started.borrow().is_empty() || !self.pulses.borrow().is_empty()
+    }
+
+    fn late_update(
+        &self,
+        idx: &'static GNode<Block, u8>,
+        tick_updatable: &mut UpdatableList,
+        tick_counter: usize,
+    ) -> Option<(u8, u8)> {
+        if tick_counter == self.last_update.get() {
+            return None;
+        }
+        self.last_update.set(tick_counter);
+
+        let old = self.output_power();
+        let mut pulses = self.pulses.borrow_mut();
+        for i in self.started.take() {
+            let mut stage = self.stages[i].get();
+            let flip = tick_counter + stage.delay as usize - 1;
+            stage.flip = Some(flip);
+            self.stages[i].set(stage);
+            pulses.insert((flip, i));
+        }
+
+        let mut due = self.due.borrow_mut();
+        while pulses.first().is_some_and(|&(t, _)| t <= tick_counter) {
+            let (_, i) = pulses.pop_first().unwrap();
+            let mut stage = self.stages[i].get();
+            stage.powered = stage.next_powered;
+            stage.flip = None;
+            self.stages[i].set(stage);
+
+            // The stage checks its input again, and the next one sees the new output.
+            due.push(i);
+            if i + 1 < self.stages.len() {
+                due.push(i + 1);
+            }
+        }
+        tick_updatable.push(idx);
+
+        let new = self.output_power();
+        (old != new).then_some((old, new))
+    }
+}
+
+impl RuntimeState for DelayLine {
+    fn state(&self) -> BlockState {
+        BlockState::DelayLine {
+            stages: self.stages.iter().map(Cell::get).collect(),
+            due: self.due.borrow().clone(),
+            last_update: self.last_update.get(),
+        }
+    }
+
+    fn set_state(&self, state: &BlockState) -> bool {
+        let BlockState::DelayLine {
+            stages,
+            due,
+            last_update,
+        } = state
+        else {
+            return false;
+        };
+        if stages.len() != self.stages.len() || due.iter().any(|&i| i >= stages.len()) {
+            return false;
+        }
+        let mut pulses = self.pulses.borrow_mut();
+        pulses.clear();
+        for (i, (stage, s)) in self.stages.iter().zip(stages).enumerate() {
+            stage.set(*s);
+            if let Some(flip) = s.flip {
+                pulses.insert((flip, i));
+            }
+        }
+        self.started.borrow_mut().clear();
+        self.due.replace(due.clone());
+        self.last_update.set(*last_update);
+        true
+    }
+}
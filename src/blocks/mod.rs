@@ -1,4 +1,7 @@
+use crate::blocks::binary_comparator::{BinaryComparator, CBinaryComparator};
+use crate::blocks::buffer::{Buffer, CBuffer};
 use crate::blocks::comparator::{CComparator, Comparator};
+use crate::blocks::delay_line::{CDelayLine, DelayLine, Stage};
 use crate::blocks::facing::Facing;
 use crate::blocks::probe::CProbe;
 use crate::blocks::redstone::{CRedstone, Redstone};
@@ -12,9 +15,13 @@ use crate::world::edge::Edge;
 use crate::world::graph::GNode;
 use crate::world::UpdatableList;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
+pub mod binary_comparator;
+pub mod buffer;
 pub mod comparator;
+pub mod delay_line;
 pub mod facing;
 pub mod probe;
 pub mod redstone;
@@ -40,6 +47,9 @@ pub enum Block {
     Repeater(Repeater),
     Comparator(Comparator),
     SRepeater(SRepeater),
+    DelayLine(DelayLine),
+    Buffer(Buffer),
+    BinaryComparator(BinaryComparator),
 }
 
 /// Blocks used during the creation of the graph structure of the world.
@@ -52,11 +62,47 @@ pub enum CBlock {
     Probe(CProbe),
     Repeater(CRepeater),
     SRepeater(CSRepeater),
+    DelayLine(CDelayLine),
+    Buffer(CBuffer),
+    BinaryComparator(CBinaryComparator),
     RedstoneBlock(CRedstoneBlock),
     Torch(CTorch),
     Comparator(CComparator),
 }
 
+/// Snapshot of the runtime fields of a `Block`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlockState {
+    Redstone {
+        signal: bool,
+    },
+    Repeater {
+        powered: bool,
+        next_powered: bool,
+        locking_signal: bool,
+        count: u8,
+        last_update: usize,
+    },
+    Comparator {
+        signal: u8,
+        next_signal: u8,
+    },
+    SRepeater {
+        powered: bool,
+        on_inputs: u8,
+    },
+    DelayLine {
+        stages: Vec<Stage>,
+        /// Stages that are updated during the next tick.
+        due: Vec<usize>,
+        last_update: usize,
+    },
+    Buffer {
+        history: u32,
+        last_update: usize,
+    },
+}
+
 pub trait OutputPower {
     fn output_power(&self) -> u8;
 }
@@ -68,6 +114,9 @@ impl OutputPower for Block {
             Block::Repeater(v) => v.output_power(),
             Block::Comparator(v) => v.output_power(),
             Block::SRepeater(v) => v.output_power(),
+            Block::DelayLine(v) => v.output_power(),
+            Block::Buffer(v) => v.output_power(),
+            Block::BinaryComparator(v) => v.output_power(),
         }
     }
 }
@@ -80,6 +129,9 @@ impl OutputPower for CBlock {
             CBlock::Torch(v) => v.output_power(),
             CBlock::Comparator(v) => v.output_power(),
             CBlock::SRepeater(v) => v.output_power(),
+            CBlock::DelayLine(v) => v.output_power(),
+            CBlock::Buffer(v) => v.output_power(),
+            CBlock::BinaryComparator(v) => v.output_power(),
             CBlock::SolidWeak(_) => unreachable!(),
             CBlock::SolidStrong(_) => unreachable!(),
             CBlock::Trigger(_) => 0,
@@ -94,6 +146,7 @@ impl Block {
         match self {
             Block::Repeater(v) => v.will_lock(),
             Block::Comparator(v) => v.output_power() > 0,
+            Block::BinaryComparator(v) => v.output_power() > 0,
             _ => unreachable!(),
         }
     }
@@ -182,6 +235,9 @@ impl BlockConnections for CBlock {
             CBlock::Torch(v) => v.can_output(facing),
             CBlock::Comparator(v) => v.can_output(facing),
             CBlock::SRepeater(_) => unreachable!(),
+            CBlock::DelayLine(_) => unreachable!(),
+            CBlock::Buffer(_) => unreachable!(),
+            CBlock::BinaryComparator(_) => unreachable!(),
         }
     }
 
@@ -197,6 +253,9 @@ impl BlockConnections for CBlock {
             CBlock::Torch(v) => v.can_input(facing),
             CBlock::Comparator(v) => v.can_input(facing),
             CBlock::SRepeater(_) => unreachable!(),
+            CBlock::DelayLine(_) => unreachable!(),
+            CBlock::Buffer(_) => unreachable!(),
+            CBlock::BinaryComparator(_) => unreachable!(),
         }
     }
 }
@@ -213,6 +272,9 @@ impl ToBlock for CBlock {
             CBlock::Torch(v) => v.to_block(on_inputs),
             CBlock::Comparator(v) => v.to_block(on_inputs),
             CBlock::SRepeater(v) => v.to_block(on_inputs),
+            CBlock::DelayLine(v) => v.to_block(on_inputs),
+            CBlock::Buffer(v) => v.to_block(on_inputs),
+            CBlock::BinaryComparator(v) => v.to_block(on_inputs),
         }
     }
 }
@@ -249,6 +311,65 @@ impl CBlock {
     }
 }
 
+impl CBlock {
+    /// Returns why `from_id` would fail on the block id, if it would.
+    pub(crate) fn check_id(id: &str) -> Result<(), String> {
+        let (name, meta) = id
+            .split_once('[')
+            .map_or((id, ""), |(x, y)| (x, y.trim_end_matches(']')));
+
+        let mut properties = HashMap::new();
+        for key_value in meta.split(',').filter(|v| !v.is_empty()) {
+            let (key, value) = key_value
+                .split_once('=')
+                .ok_or_else(|| format!("malformed property {key_value} of {id}"))?;
+            properties.insert(key, value);
+        }
+
+        let property = |key: &str, values: &[&str]| match properties.get(key) {
+            Some(v) if values.is_empty() || values.contains(v) => Ok(()),
+            Some(v) => Err(format!("invalid {key}={v} of {id}")),
+            None => Err(format!("missing property {key} of {id}")),
+        };
+        let facings = ["north", "east", "south", "west"];
+        let booleans = ["true", "false"];
+
+        match name {
+            "minecraft:redstone_wire" => {
+                property("power", &[])?;
+                if properties["power"].parse::<u8>().is_err() {
+                    return Err(format!("invalid power={} of {id}", properties["power"]));
+                }
+                for side in facings {
+                    property(side, &[])?;
+                }
+            }
+            "minecraft:gold_block"
+            | "minecraft:lightning_rod"
+            | "minecraft:diamond_block"
+            | "minecraft:redstone_block" => {}
+            "minecraft:redstone_torch" => property("lit", &booleans)?,
+            "minecraft:redstone_wall_torch" => {
+                property("lit", &booleans)?;
+                property("facing", &facings)?;
+            }
+            "minecraft:comparator" => {
+                property("facing", &facings)?;
+                property("mode", &["compare", "subtract"])?;
+            }
+            "minecraft:repeater" => {
+                property("powered", &booleans)?;
+                property("facing", &facings)?;
+                property("delay", &["1", "2", "3", "4"])?;
+            }
+            name if SOLID_BLOCKS.contains(name) || TRANSPARENT_BLOCKS.contains(name) => {}
+            _ => return Err(format!("undefined block id {name}")),
+        }
+
+        Ok(())
+    }
+}
+
 impl CBlock {
     pub fn is_transparent(&self) -> bool {
         match self {
@@ -262,6 +383,9 @@ impl CBlock {
             CBlock::Torch { .. } => true,
             CBlock::Probe { .. } => false,
             CBlock::SRepeater { .. } => unreachable!(),
+            CBlock::DelayLine { .. } => unreachable!(),
+            CBlock::Buffer { .. } => unreachable!(),
+            CBlock::BinaryComparator { .. } => unreachable!(),
         }
     }
 
@@ -274,9 +398,7 @@ impl CBlock {
             return None;
         }
 
-        let Some(input_side) = target.can_input(facing) else {
-            return None;
-        };
+        let input_side = target.can_input(facing)?;
 
         if !can_connect(self, target, facing) {
             return None;
@@ -286,6 +408,39 @@ impl CBlock {
     }
 }
 
+pub trait RuntimeState {
+    fn state(&self) -> BlockState;
+
+    /// Restores a snapshot taken with `state`, returns `false` if it belongs to another kind of block.
+    fn set_state(&self, state: &BlockState) -> bool;
+}
+
+impl RuntimeState for Block {
+    fn state(&self) -> BlockState {
+        match self {
+            Block::Redstone(v) => v.state(),
+            Block::Repeater(v) => v.state(),
+            Block::Comparator(v) => v.state(),
+            Block::SRepeater(v) => v.state(),
+            Block::DelayLine(v) => v.state(),
+            Block::Buffer(v) => v.state(),
+            Block::BinaryComparator(v) => v.state(),
+        }
+    }
+
+    fn set_state(&self, state: &BlockState) -> bool {
+        match self {
+            Block::Redstone(v) => v.set_state(state),
+            Block::Repeater(v) => v.set_state(state),
+            Block::Comparator(v) => v.set_state(state),
+            Block::SRepeater(v) => v.set_state(state),
+            Block::DelayLine(v) => v.set_state(state),
+            Block::Buffer(v) => v.set_state(state),
+            Block::BinaryComparator(v) => v.set_state(state),
+        }
+    }
+}
+
 pub trait Updatable {
     fn update(
         &self,
@@ -315,6 +470,9 @@ impl Updatable for Block {
             Block::Comparator(v) => v.update(idx, tick_updatable, up),
             Block::Redstone(v) => v.update(idx, tick_updatable, up),
             Block::SRepeater(v) => v.update(idx, tick_updatable, up),
+            Block::DelayLine(v) => v.update(idx, tick_updatable, up),
+            Block::Buffer(v) => v.update(idx, tick_updatable, up),
+            Block::BinaryComparator(v) => v.update(idx, tick_updatable, up),
         }
     }
 
@@ -329,6 +487,9 @@ impl Updatable for Block {
             Block::Comparator(v) => v.late_update(idx, tick_updatable, tick_counter),
             Block::Redstone(_) => unreachable!(),
             Block::SRepeater(v) => v.late_update(idx, tick_updatable, tick_counter),
+            Block::DelayLine(v) => v.late_update(idx, tick_updatable, tick_counter),
+            Block::Buffer(v) => v.late_update(idx, tick_updatable, tick_counter),
+            Block::BinaryComparator(v) => v.late_update(idx, tick_updatable, tick_counter),
         }
     }
 }
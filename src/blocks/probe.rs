@@ -27,26 +27,31 @@ impl ToBlock for CProbe {
 
 impl CProbe {
     pub fn update_from_tile(&mut self, p: (usize, usize, usize), tile_map: &TileMap) {
-        self.name = neighbours(p)
-            .find_map(|p| {
-                tile_map.get(&p).and_then(|b| {
-                    if b.id == "minecraft:sign" {
-                        if let Some(Value::String(s)) = b.props.get("Text1") {
-                            let j = serde_json::from_str::<serde_json::Value>(s).unwrap();
-                            return Some(
-                                j.as_object()
-                                    .unwrap()
-                                    .get("text")
-                                    .unwrap()
-                                    .as_str()
-                                    .unwrap()
-                                    .to_string(),
-                            );
-                        }
+        self.name = name_from_signs(p, tile_map);
+    }
+}
+
+/// Returns the first line of any sign neighbouring the given position, or the position itself.
+pub fn name_from_signs(p: (usize, usize, usize), tile_map: &TileMap) -> String {
+    neighbours(p)
+        .find_map(|p| {
+            tile_map.get(&p).and_then(|b| {
+                if b.id == "minecraft:sign" {
+                    if let Some(Value::String(s)) = b.props.get("Text1") {
+                        let j = serde_json::from_str::<serde_json::Value>(s).unwrap();
+                        return Some(
+                            j.as_object()
+                                .unwrap()
+                                .get("text")
+                                .unwrap()
+                                .as_str()
+                                .unwrap()
+                                .to_string(),
+                        );
                     }
-                    None
-                })
+                }
+                None
             })
-            .unwrap_or(format!("{},{},{}", p.0, p.1, p.2))
-    }
+        })
+        .unwrap_or(format!("{},{},{}", p.0, p.1, p.2))
 }
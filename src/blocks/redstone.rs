@@ -1,5 +1,8 @@
 use crate::blocks::facing::Facing;
-use crate::blocks::{Block, BlockConnections, CBlock, InputSide, OutputPower, ToBlock, Updatable};
+use crate::blocks::{
+    Block, BlockConnections, BlockState, CBlock, InputSide, OutputPower, RuntimeState, ToBlock,
+    Updatable,
+};
 use crate::world::data::WorldData;
 use crate::world::edge::Edge;
 use crate::world::graph::GNode;
@@ -104,6 +107,22 @@ impl Updatable for Redstone {
     }
 }
 
+impl RuntimeState for Redstone {
+    fn state(&self) -> BlockState {
+        BlockState::Redstone {
+            signal: self.signal.get(),
+        }
+    }
+
+    fn set_state(&self, state: &BlockState) -> bool {
+        let BlockState::Redstone { signal } = *state else {
+            return false;
+        };
+        self.signal.set(signal);
+        true
+    }
+}
+
 impl From<HashMap<&str, &str>> for CRedstone {
     fn from(meta: HashMap<&str, &str>) -> Self {
         CRedstone {
@@ -130,7 +149,7 @@ impl CRedstone {
         (x, y, z): (usize, usize, usize),
         blocks: &mut CBlockGraph,
         world: &WorldData,
-        indexes: &Vec<Vec<Vec<Vec<NodeIndex>>>>,
+        indexes: &[Vec<Vec<Vec<NodeIndex>>>],
     ) {
         let idx = indexes[x][y][z][0];
         let top = (x, y.wrapping_add(1), z);
@@ -1,5 +1,7 @@
 use crate::blocks::facing::Facing;
-use crate::blocks::{Block, BlockConnections, InputSide, OutputPower, ToBlock, Updatable};
+use crate::blocks::{
+    Block, BlockConnections, BlockState, InputSide, OutputPower, RuntimeState, ToBlock, Updatable,
+};
 use crate::world::graph::GNode;
 use crate::world::UpdatableList;
 use std::cell::Cell;
@@ -172,13 +174,123 @@ impl Updatable for Repeater {
     }
 }
 
+impl RuntimeState for Repeater {
+    fn state(&self) -> BlockState {
+        BlockState::Repeater {
+            powered: self.powered.get(),
+            next_powered: self.next_powered.get(),
+            locking_signal: self.locking_signal.get(),
+            count: self.count.get(),
+            last_update: self.last_update.get(),
+        }
+    }
+
+    fn set_state(&self, state: &BlockState) -> bool {
+        let BlockState::Repeater {
+            powered,
+            next_powered,
+            locking_signal,
+            count,
+            last_update,
+        } = *state
+        else {
+            return false;
+        };
+        self.powered.set(powered);
+        self.next_powered.set(next_powered);
+        self.locking_signal.set(locking_signal);
+        self.count.set(count);
+        self.last_update.set(last_update);
+        true
+    }
+}
+
+/// Effect of the next `update` of a repeater whose inputs do not change.
+#[derive(Copy, Clone, Debug)]
+pub struct IdleUpdate {
+    /// Number of ticks the repeater counts down before changing its output, `None` if it does not count.
+    pub ticks_left: Option<u8>,
+
+    /// Whether the update pushes the repeaters this repeater outputs to.
+    pub pushes_neighbours: bool,
+}
+
 impl Repeater {
     pub fn will_lock(&self) -> bool {
         self.locking_signal.get()
     }
+
+    pub fn delay(&self) -> u8 {
+        self.delay
+    }
+
+    /// Returns the effect of `update` if it changes nothing but the `count` of this repeater.
+    pub fn idle_update(&self, idx: &'static GNode<Block, u8>) -> Option<IdleUpdate> {
+        let locked_now = idx
+            .incoming_side
+            .iter()
+            .any(|e| e.node.weight.output_power().saturating_sub(e.weight) > 0);
+
+        if locked_now {
+            return Some(IdleUpdate {
+                ticks_left: None,
+                pushes_neighbours: false,
+            });
+        }
+
+        let s_new = idx
+            .incoming_rear
+            .iter()
+            .any(|e| e.node.weight.output_power().saturating_sub(e.weight) > 0);
+        let locked_next_tick = idx.incoming_side.iter().any(|e| e.node.weight.will_lock());
+
+        // The next signal and count must be left untouched.
+        if s_new != self.next_powered.get() && (s_new || self.count.get() == 0) {
+            return None;
+        }
+
+        let locking_signal = if locked_next_tick {
+            self.powered.get()
+        } else if self.count.get() + 1 == self.delay {
+            self.next_powered.get()
+        } else {
+            self.powered.get()
+        };
+        if locking_signal != self.locking_signal.get() {
+            return None;
+        }
+
+        let ticks_left = if self.powered.get() == self.next_powered.get() {
+            None
+        } else if self.count.get() + 1 < self.delay {
+            Some(self.delay - 1 - self.count.get())
+        } else {
+            return None;
+        };
+
+        Some(IdleUpdate {
+            ticks_left,
+            pushes_neighbours: locked_next_tick == self.locking_signal.get(),
+        })
+    }
+
+    /// Advances the count of a repeater for `ticks` idle ticks, the last of which was `last_tick`.
+    pub fn skip(&self, ticks: u8, last_tick: usize) {
+        self.count.set(self.count.get() + ticks);
+        self.last_update.set(last_tick);
+    }
 }
 
 impl CRepeater {
+    /// Creates a repeater that is not part of a schematic, which faces north.
+    pub fn with_delay(delay: u8, powered: bool) -> CRepeater {
+        CRepeater {
+            powered,
+            delay,
+            facing: Facing::North,
+        }
+    }
+
     pub fn delay(&self) -> u8 {
         self.delay
     }
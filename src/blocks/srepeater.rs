@@ -1,4 +1,4 @@
-use crate::blocks::{Block, OutputPower, ToBlock, Updatable};
+use crate::blocks::{Block, BlockState, OutputPower, RuntimeState, ToBlock, Updatable};
 use crate::world::graph::GNode;
 use crate::world::UpdatableList;
 use std::cell::Cell;
@@ -83,3 +83,21 @@ impl Updatable for SRepeater {
         }
     }
 }
+
+impl RuntimeState for SRepeater {
+    fn state(&self) -> BlockState {
+        BlockState::SRepeater {
+            powered: self.powered.get(),
+            on_inputs: self.on_inputs.get(),
+        }
+    }
+
+    fn set_state(&self, state: &BlockState) -> bool {
+        let BlockState::SRepeater { powered, on_inputs } = *state else {
+            return false;
+        };
+        self.powered.set(powered);
+        self.on_inputs.set(on_inputs);
+        true
+    }
+}
@@ -36,6 +36,20 @@ impl BlockConnections for CTorch {
         }
     }
 }
+impl CTorch {
+    /// Creates a standing torch that is not part of a schematic.
+    pub fn with_lit(lit: bool) -> CTorch {
+        CTorch {
+            lit,
+            facing: Facing::Up,
+        }
+    }
+
+    pub fn is_lit(&self) -> bool {
+        self.lit
+    }
+}
+
 impl ToBlock for CTorch {
     fn to_block(&self, on_inputs: u8) -> Block {
         Block::SRepeater(SRepeater {
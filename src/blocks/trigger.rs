@@ -1,9 +1,14 @@
 use crate::blocks::facing::Facing;
+use crate::blocks::probe::name_from_signs;
 use crate::blocks::redstone::Redstone;
 use crate::blocks::{Block, BlockConnections, InputSide, ToBlock};
+use crate::world::data::TileMap;
 
-#[derive(Copy, Clone, Debug, Default)]
-pub struct CTrigger {}
+#[derive(Clone, Debug, Default)]
+pub struct CTrigger {
+    /// Name of the trigger. Uses the first line of any neighbouring sign it finds.
+    pub name: String,
+}
 
 impl BlockConnections for CTrigger {
     fn can_output(&self, _facing: Facing) -> bool {
@@ -19,3 +24,9 @@ impl ToBlock for CTrigger {
         Block::Redstone(Redstone::default())
     }
 }
+
+impl CTrigger {
+    pub fn update_from_tile(&mut self, p: (usize, usize, usize), tile_map: &TileMap) {
+        self.name = name_from_signs(p, tile_map);
+    }
+}
@@ -1,17 +1,14 @@
 use crate::blocks::CBlock;
+use crate::world::provenance::Provenance;
 use crate::world::CBlockGraph;
-use petgraph::prelude::{EdgeRef, NodeIndex};
+use itertools::Itertools;
+use petgraph::prelude::EdgeRef;
 use petgraph::visit::{IntoEdgeReferences, IntoNodeReferences};
-use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
-pub fn write_dot(
-    cblocks: &CBlockGraph,
-    pos: &HashMap<NodeIndex, (isize, isize, isize)>,
-    path: &Path,
-) {
+pub fn write_dot(cblocks: &CBlockGraph, provenance: &Provenance, path: &Path) {
     let mut w = File::create(path).unwrap();
     writeln!(w, "digraph world {{").unwrap();
     for (node, cblock) in cblocks.node_references() {
@@ -23,16 +20,20 @@ pub fn write_dot(
             CBlock::Probe(_) => ("probe", "#42D4F4"),
             CBlock::Repeater(_) => ("repeater", "#469990"),
             CBlock::SRepeater(_) => ("srepeater", "#3CB44B"),
+            CBlock::DelayLine(_) => ("delay_line", "#AAFFC3"),
+            CBlock::Buffer(_) => ("buffer", "#BFEF45"),
             CBlock::RedstoneBlock(_) => ("redstone_block", "#F58231"),
             CBlock::Torch(_) => ("torch", "#E6194B"),
             CBlock::Comparator(_) => ("comparator", "#911EB4"),
+            CBlock::BinaryComparator(_) => ("binary_comparator", "#DCBEFF"),
         };
-        if let Some(pos) = pos.get(&node) {
+        let pos = provenance.node(node);
+        if !pos.is_empty() {
             writeln!(
                 w,
-                "{}[label=\"{label}\",color={color}, world_pos=\"{:?}\"];",
+                "{}[label=\"{label}\",color={color}, world_pos=\"{}\"];",
                 node.index(),
-                pos
+                pos.iter().map(|p| format!("{p:?}")).join(" ")
             )
             .unwrap();
         } else {
@@ -45,14 +46,24 @@ pub fn write_dot(
         } else {
             "#000000"
         };
-        writeln!(
+        write!(
             w,
-            "{} -> {}[label=\"{}\",color={color}];",
+            "{} -> {}[label=\"{}\",color={color}",
             edge.source().index(),
             edge.target().index(),
             edge.weight().strength_loss()
         )
         .unwrap();
+        let path = provenance.edge(edge.id());
+        if !path.is_empty() {
+            write!(
+                w,
+                ", world_path=\"{}\"",
+                path.iter().map(|p| format!("{p:?}")).join(" ")
+            )
+            .unwrap();
+        }
+        writeln!(w, "];").unwrap();
     }
 
     writeln!(w, "}}").unwrap();
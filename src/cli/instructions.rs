@@ -80,12 +80,7 @@ fn parse_num(s: &str) -> Option<(usize, &str)> {
 }
 
 fn parse_literal<'a>(s: &'a str, k: &str) -> Option<&'a str> {
-    let s = s.trim_start();
-    if s.starts_with(k) {
-        Some(&s[k.len()..])
-    } else {
-        None
-    }
+    s.trim_start().strip_prefix(k)
 }
 
 #[cfg(test)]
@@ -2,12 +2,14 @@ mod dot;
 mod instructions;
 pub mod runner;
 
-use clap::Parser;
+use crate::world::prune::PruneOptions;
+use clap::{Parser, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// Schematic file to use as input.
+    /// Schematic file to use as input, a text schematic if it ends in `.tschem`, or a netlist if it
+    /// ends in `.json` or `.blif`.
     #[arg(short, long)]
     input: String,
 
@@ -22,4 +24,111 @@ pub struct Args {
     /// Output file to use for producing a dot file.
     #[arg(short, long)]
     dot: Option<String>,
+
+    /// Output file to use for generating Rust source that simulates the circuit.
+    #[arg(long)]
+    codegen: Option<String>,
+
+    /// Output file to use for exporting a Verilog netlist of the circuit. The primitives it uses are
+    /// written to `redstone_primitives.v` in the same directory.
+    #[arg(long)]
+    verilog: Option<String>,
+
+    /// Maximum number of ticks to search for a cycle in the runtime state, after running the simulation.
+    #[arg(long)]
+    oscillation: Option<usize>,
+
+    /// Print statistics of the pruning passes.
+    #[arg(long, value_enum)]
+    stats: Option<ReportFormat>,
+
+    /// Print the minimum and maximum delay from every trigger to every probe, and the critical path.
+    #[arg(long, value_enum)]
+    timing: Option<ReportFormat>,
+
+    /// Print the boolean expression of every probe in terms of the triggers, or its truth table.
+    #[arg(long, value_enum)]
+    logic: Option<LogicFormat>,
+
+    // Dust and solid blocks are always replaced by direct edges, since a `World` can not simulate
+    // them, so there is no flag to keep them.
+    /// Do not merge duplicate edges between blocks.
+    #[arg(long)]
+    no_prune_duplicate_edges: bool,
+
+    /// Do not remove edges that lose all signal strength.
+    #[arg(long)]
+    no_prune_untraversable_edges: bool,
+
+    /// Do not merge identical components that have the same parent.
+    #[arg(long)]
+    no_prune_groups: bool,
+
+    /// Do not remove blocks that cannot affect any probe.
+    #[arg(long)]
+    no_prune_irrelevant: bool,
+
+    /// Do not merge gates of the same kind that have exactly the same inputs.
+    #[arg(long)]
+    no_prune_identical: bool,
+
+    /// Replace chains of repeaters by delay lines, which cannot be used with --codegen or --verilog.
+    #[arg(long, conflicts_with_all = ["codegen", "verilog"])]
+    prune_delay_lines: bool,
+
+    /// Do not replace repeaters with a delay of one tick by simple repeaters.
+    #[arg(long)]
+    no_prune_simple_repeaters: bool,
+
+    /// Do not remove rear edges into subtractors that are cancelled by a side edge from the same block.
+    #[arg(long)]
+    no_prune_subtractor_edges: bool,
+
+    /// Do not remove blocks with a constant output.
+    #[arg(long)]
+    no_prune_constants: bool,
+
+    /// Do not remove blocks without inputs or outputs.
+    #[arg(long)]
+    no_prune_dead_nodes: bool,
+
+    /// Do not replace comparators with binary inputs by binary comparators.
+    #[arg(long)]
+    no_prune_binary_comparators: bool,
+
+    /// Replace chains of torches and simple repeaters by buffers, which cannot be used with --codegen
+    /// or --verilog.
+    #[arg(long, conflicts_with_all = ["codegen", "verilog"])]
+    prune_buffers: bool,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum ReportFormat {
+    Table,
+    Json,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum LogicFormat {
+    Expr,
+    Table,
+}
+
+impl Args {
+    fn prune_options(&self) -> PruneOptions {
+        PruneOptions {
+            duplicate_edges: !self.no_prune_duplicate_edges,
+            untraversable_edges: !self.no_prune_untraversable_edges,
+            groups: !self.no_prune_groups,
+            irrelevant: !self.no_prune_irrelevant,
+            identical: !self.no_prune_identical,
+            delay_lines: self.prune_delay_lines,
+            simple_repeaters: !self.no_prune_simple_repeaters,
+            subtractor_edges: !self.no_prune_subtractor_edges,
+            constants: !self.no_prune_constants,
+            dead_nodes: !self.no_prune_dead_nodes,
+            binary_comparators: !self.no_prune_binary_comparators,
+            buffers: self.prune_buffers,
+        }
+    }
 }
@@ -1,6 +1,10 @@
 use crate::cli::dot::write_dot;
 use crate::cli::instructions::{parse, Instruction, InstructionAst};
-use crate::cli::Args;
+use crate::cli::{Args, LogicFormat, ReportFormat};
+use crate::world::codegen::{generate, Target};
+use crate::world::netlist::Netlist;
+use crate::world::text;
+use crate::world::verilog::{netlist, PRIMITIVES};
 use crate::world::World;
 use std::collections::HashMap;
 use std::fs::File;
@@ -8,10 +12,81 @@ use std::path::Path;
 use vcd::{IdCode, SimulationCommand, TimescaleUnit};
 
 pub fn run(args: Args) {
-    let mut world = World::from(File::open(args.input).expect("Could not open schematic file."));
+    let mut world = match Path::new(&args.input).extension().and_then(|e| e.to_str()) {
+        Some(format @ ("json" | "blif")) => {
+            let s = std::fs::read_to_string(&args.input).expect("Could not open netlist file.");
+            let netlist = if format == "json" {
+                Netlist::from_json(&s)
+            } else {
+                Netlist::from_blif(&s)
+            };
+            netlist
+                .and_then(|n| World::from_netlist(&n, &args.prune_options()))
+                .unwrap_or_else(|e| panic!("Could not build netlist: {e}"))
+        }
+        Some(extension) if extension == text::EXTENSION => {
+            let s = std::fs::read_to_string(&args.input).expect("Could not open schematic file.");
+            text::parse(&s)
+                .unwrap_or_else(|e| panic!("Could not read text schematic: {e}"))
+                .build_with_prune_options(&args.prune_options())
+        }
+        _ => World::from_file_with_prune_options(
+            File::open(&args.input).expect("Could not open schematic file."),
+            &args.prune_options(),
+        ),
+    };
+
+    match args.stats {
+        Some(ReportFormat::Table) => println!("{}", world.prune_stats()),
+        Some(ReportFormat::Json) => println!(
+            "{}",
+            serde_json::to_string_pretty(world.prune_stats()).unwrap()
+        ),
+        None => {}
+    }
+
+    match args.timing {
+        Some(ReportFormat::Table) => println!("{}", world.timing()),
+        Some(ReportFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&world.timing()).unwrap())
+        }
+        None => {}
+    }
+
+    if let Some(format) = args.logic {
+        for logic in world.probe_logic() {
+            let sequential = if logic.is_sequential() {
+                " (sequential)"
+            } else {
+                ""
+            };
+            match (format, logic.truth_table()) {
+                (LogicFormat::Table, Some(table)) => println!("{table}\n"),
+                _ => println!("{}{sequential} = {}", logic.probe, logic.expr),
+            }
+        }
+    }
 
     if let Some(dot) = args.dot {
-        write_dot(&world.cblocks, &world.cblock_positions, Path::new(&dot));
+        write_dot(&world.cblocks, &world.provenance, Path::new(&dot));
+    }
+
+    if let Some(codegen) = args.codegen {
+        let code = generate(&world, Target::Module)
+            .unwrap_or_else(|e| panic!("Could not generate code: {e}"));
+        std::fs::write(codegen, code).expect("Could not write generated code.");
+    }
+
+    if let Some(verilog) = args.verilog {
+        let path = Path::new(&verilog);
+        let module = Path::new(&args.input)
+            .file_stem()
+            .map_or("top".into(), |s| s.to_string_lossy());
+        let netlist = netlist(&world, &module)
+            .unwrap_or_else(|e| panic!("Could not export Verilog netlist: {e}"));
+        std::fs::write(path, netlist).expect("Could not write Verilog netlist.");
+        std::fs::write(path.with_file_name("redstone_primitives.v"), PRIMITIVES)
+            .expect("Could not write Verilog primitives.");
     }
 
     if let Some(w) = args.wave {
@@ -44,32 +119,56 @@ pub fn run(args: Args) {
         w.end().unwrap();
 
         // Write the data values
-        let mut i = 0;
-        run_ast(&mut world, &ast, &mut |probes| {
-            i += 1;
-            w.timestamp(i).unwrap();
-            for (probe, v) in probes {
-                w.change_scalar(probe_indices[probe], v).unwrap();
+        let start = world.tick_counter();
+        let mut last = start;
+        run_ast(&mut world, &ast, &mut |tick, probe, v| {
+            if tick != last {
+                last = tick;
+                w.timestamp((tick - start) as u64).unwrap();
             }
+            w.change_scalar(probe_indices[probe], v).unwrap();
         });
+        if world.tick_counter() != last {
+            w.timestamp((world.tick_counter() - start) as u64).unwrap();
+        }
+    }
+
+    if let Some(max_ticks) = args.oscillation {
+        match world.detect_oscillation(max_ticks) {
+            Some(o) => {
+                println!(
+                    "Oscillation with a period of {} ticks, starting at tick {}.",
+                    o.period, o.start
+                );
+                for pos in o.positions {
+                    println!("Toggling block at {pos:?}");
+                }
+            }
+            None => println!("No oscillation found within {max_ticks} ticks."),
+        }
     }
 }
 
-fn run_ast(world: &mut World, ast: &InstructionAst, write: &mut impl FnMut(HashMap<&str, bool>)) {
+fn run_ast(world: &mut World, ast: &InstructionAst, write: &mut impl FnMut(usize, &str, bool)) {
     match ast {
         InstructionAst::Instruction(Instruction::Trigger) => {
             world.step_with_trigger();
-            write(world.get_probes());
+            let tick = world.tick_counter();
+            for (probe, v) in world.probe_changes() {
+                write(tick, probe, v);
+            }
         }
         InstructionAst::Instruction(Instruction::Step) => {
-            world.step();
-            write(world.get_probes());
+            world.step_n_with(1, &mut *write);
         }
         InstructionAst::Sequence(v) => {
             for i in v {
                 run_ast(world, i, write);
             }
         }
+        InstructionAst::Repeat(i, n) if **i == InstructionAst::Instruction(Instruction::Step) => {
+            world.step_n_with(*n, &mut *write);
+        }
         InstructionAst::Repeat(i, n) => {
             for _ in 0..*n {
                 run_ast(world, i, write);
@@ -1,10 +1,6 @@
-use crate::cli::runner::run;
-use crate::cli::Args;
 use clap::Parser;
-
-mod blocks;
-mod cli;
-mod world;
+use redstone_simulator::cli::runner::run;
+use redstone_simulator::cli::Args;
 
 fn main() {
     run(Args::parse())
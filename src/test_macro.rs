@@ -1,16 +1,28 @@
+use crate::world::text::EXTENSION;
+use crate::world::World;
+use itertools::Itertools;
+use std::fs::{self, File};
+use std::path::Path;
+
 pub const F: bool = false;
 pub const T: bool = true;
 
+/// Loads `schematics/{name}.schem`, or the text schematic `schematics/{name}.tschem` if it exists.
+pub fn load(name: &str) -> World {
+    let text = format!("./schematics/{name}.{EXTENSION}");
+    if Path::new(&text).exists() {
+        World::from_text(&fs::read_to_string(&text).unwrap()).unwrap()
+    } else {
+        World::from(File::open(format!("./schematics/{name}.schem")).unwrap())
+    }
+}
+
 #[macro_export]
 macro_rules! test {
     ($file:literal, $name:ident, $triggers: expr; $($b:expr),*) => {
         #[test]
         fn $name() {
-            use std::fs::File;
-            use redstone_simulator::world::World;
-
-            let file = File::open(format!("./schematics/{}.schem", $file)).unwrap();
-            let mut world = World::from(file);
+            let mut world = redstone_simulator::test_macro::load($file);
 
             let mut triggers = $triggers;
 
@@ -29,3 +41,178 @@ macro_rules! test {
         test!{$file, $name, 1; $($b),*}
     };
 }
+
+/// Expected waveforms of several probes, together with the values of named triggers, checked one
+/// tick at a time by `trace!`.
+#[derive(Clone, Debug, Default)]
+pub struct Trace {
+    triggers: Vec<String>,
+    probes: Vec<String>,
+
+    /// Triggers to set and expected probe values of every tick.
+    ticks: Vec<(Vec<Option<bool>>, Vec<bool>)>,
+}
+
+impl Trace {
+    pub fn new(triggers: &[&str], probes: &[&str]) -> Trace {
+        Trace {
+            triggers: triggers.iter().map(|s| s.to_string()).collect(),
+            probes: probes.iter().map(|s| s.to_string()).collect(),
+            ticks: vec![],
+        }
+    }
+
+    /// Adds a tick, which starts with the given probe values, after which the triggers that are not
+    /// `None` are set and kept until they are set again.
+    pub fn tick(mut self, triggers: &[Option<bool>], probes: &[bool]) -> Trace {
+        assert_eq!(
+            triggers.len(),
+            self.triggers.len(),
+            "Wrong number of triggers."
+        );
+        assert_eq!(probes.len(), self.probes.len(), "Wrong number of probes.");
+        self.ticks.push((triggers.to_vec(), probes.to_vec()));
+        self
+    }
+
+    /// Runs the ticks on the world, and panics with the expected and actual waveforms if any probe
+    /// differs from what is expected.
+    pub fn check(&self, world: &mut World) {
+        let mut actual = vec![];
+        for (triggers, _) in &self.ticks {
+            actual.push(
+                self.probes
+                    .iter()
+                    .map(|p| {
+                        world
+                            .get_probe(p)
+                            .unwrap_or_else(|| panic!("No probe named {p}."))
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            for (name, v) in self.triggers.iter().zip(triggers) {
+                if let Some(v) = *v {
+                    assert!(world.set_trigger(name, v), "No trigger named {name}.");
+                }
+            }
+            world.step();
+        }
+
+        let expected = self
+            .ticks
+            .iter()
+            .map(|(_, p)| p.clone())
+            .collect::<Vec<_>>();
+        if actual != expected {
+            panic!("{}", self.diff(&actual));
+        }
+    }
+
+    /// Formats the waveforms of the triggers, and the expected and actual waveforms of the probes,
+    /// marking the ticks in which they differ.
+    fn diff(&self, actual: &[Vec<bool>]) -> String {
+        let width = self
+            .triggers
+            .iter()
+            .chain(&self.probes)
+            .map(|s| s.len())
+            .max()
+            .unwrap_or(0)
+            + " expected".len();
+        let wave = |values: &mut dyn Iterator<Item = Option<bool>>| {
+            values
+                .map(|v| match v {
+                    Some(true) => '1',
+                    Some(false) => '0',
+                    None => '.',
+                })
+                .collect::<String>()
+        };
+
+        let mut lines = vec![format!(
+            "{:width$} {}",
+            "tick",
+            (0..self.ticks.len()).map(|t| t % 10).join("")
+        )];
+        for (i, name) in self.triggers.iter().enumerate() {
+            let values = &mut self.ticks.iter().map(|(t, _)| t[i]);
+            lines.push(format!("{name:width$} {}", wave(values)));
+        }
+        for (i, name) in self.probes.iter().enumerate() {
+            let expected = &mut self.ticks.iter().map(|(_, p)| Some(p[i]));
+            lines.push(format!(
+                "{:width$} {}",
+                format!("{name} expected"),
+                wave(expected)
+            ));
+            if actual
+                .iter()
+                .zip(&self.ticks)
+                .any(|(a, (_, p))| a[i] != p[i])
+            {
+                let values = &mut actual.iter().map(|a| Some(a[i]));
+                lines.push(format!("{:>width$} {}", "actual", wave(values)));
+                let marks = actual
+                    .iter()
+                    .zip(&self.ticks)
+                    .map(|(a, (_, p))| if a[i] != p[i] { '^' } else { ' ' })
+                    .collect::<String>();
+                lines.push(format!("{:width$} {}", "", marks.trim_end()));
+            }
+        }
+        format!(
+            "Probes differ from the expected trace:\n{}",
+            lines.join("\n")
+        )
+    }
+}
+
+/// Declares a test that loads a schematic like `test!`, and checks a table of ticks. Every row
+/// sets the named triggers to `T`, `F` or leaves them as they are with `_`, after checking the
+/// named probes at the start of the tick. Names are identifiers or string literals.
+///
+/// ```ignore
+/// trace!("repeater", repeater_trace; [a] => [repeater_1t, "repeater_2t"];
+///     [T] => [F, F],
+///     [F] => [F, F],
+///     [_] => [T, F],
+/// );
+/// ```
+#[macro_export]
+macro_rules! trace {
+    ($file:literal, $name:ident; [$($trigger:tt),*] => [$($probe:tt),*];
+     $([$($t:tt),*] => [$($p:expr),*]),* $(,)?) => {
+        #[test]
+        fn $name() {
+            let mut world = redstone_simulator::test_macro::load($file);
+            redstone_simulator::test_macro::Trace::new(
+                &[$(redstone_simulator::trace_name!($trigger)),*],
+                &[$(redstone_simulator::trace_name!($probe)),*],
+            )
+            $(.tick(&[$(redstone_simulator::trace_input!($t)),*], &[$($p),*]))*
+            .check(&mut world);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! trace_name {
+    ($name:ident) => {
+        stringify!($name)
+    };
+    ($name:literal) => {
+        $name
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! trace_input {
+    (_) => {
+        None
+    };
+    ($v:expr) => {
+        Some($v)
+    };
+}
@@ -0,0 +1,152 @@
+use crate::blocks::CBlock;
+use crate::world::create::cblock_graph;
+use crate::world::provenance::Position;
+use crate::world::prune::PruneOptions;
+use crate::world::schematic::{Metadata, SchemBlockEntity, SchemFormat};
+use crate::world::World;
+use itertools::iproduct;
+use nbt::Value;
+use std::collections::HashMap;
+
+/// Places blocks by coordinate and block state, to build a `World` without a schematic file.
+///
+/// The blocks are turned into a `SchemFormat`, so the world is built exactly like one loaded from
+/// a file. Comparators and furnaces get the block entities they need, and triggers and probes take
+/// their names from the builder instead of from signs.
+#[derive(Clone, Debug, Default)]
+pub struct WorldBuilder {
+    blocks: HashMap<Position, String>,
+
+    /// Output signal of the comparators that are not off.
+    signals: HashMap<Position, u8>,
+
+    names: HashMap<Position, String>,
+}
+
+impl WorldBuilder {
+    pub fn new() -> WorldBuilder {
+        WorldBuilder::default()
+    }
+
+    /// Places the block with the given id, e.g. `minecraft:repeater[facing=north,delay=2]`,
+    /// replacing any block at the position.
+    pub fn set(&mut self, pos: Position, id: &str) -> &mut WorldBuilder {
+        self.blocks.insert(pos, id.to_string());
+        self
+    }
+
+    /// Places a trigger with the given name.
+    pub fn trigger(&mut self, pos: Position, name: &str) -> &mut WorldBuilder {
+        self.name(pos, name).set(pos, "minecraft:gold_block")
+    }
+
+    /// Places a probe with the given name.
+    pub fn probe(&mut self, pos: Position, name: &str) -> &mut WorldBuilder {
+        self.name(pos, name).set(pos, "minecraft:diamond_block")
+    }
+
+    /// Names the trigger or probe at the position.
+    pub fn name(&mut self, pos: Position, name: &str) -> &mut WorldBuilder {
+        self.names.insert(pos, name.to_string());
+        self
+    }
+
+    /// Sets the signal strength the comparator at the position outputs initially.
+    pub fn comparator_signal(&mut self, pos: Position, signal: u8) -> &mut WorldBuilder {
+        self.signals.insert(pos, signal);
+        self
+    }
+
+    /// Returns the schematic of the smallest box around every block, offset to keep their
+    /// coordinates.
+    pub fn schematic(&self) -> SchemFormat {
+        let min = |f: fn(&Position) -> isize| self.blocks.keys().map(f).min().unwrap_or(0);
+        let max = |f: fn(&Position) -> isize| self.blocks.keys().map(f).max().unwrap_or(-1);
+        let offset = (min(|p| p.0), min(|p| p.1), min(|p| p.2));
+        let size = (
+            max(|p| p.0) - offset.0 + 1,
+            max(|p| p.1) - offset.1 + 1,
+            max(|p| p.2) - offset.2 + 1,
+        );
+
+        let mut palette: HashMap<String, i32> = HashMap::new();
+        let mut block_data = vec![];
+        let mut block_entities = vec![];
+        for (y, z, x) in iproduct!(0..size.1, 0..size.2, 0..size.0) {
+            let pos = (x + offset.0, y + offset.1, z + offset.2);
+            let id = self
+                .blocks
+                .get(&pos)
+                .map_or("minecraft:air", String::as_str);
+
+            let next = palette.len() as i32;
+            let mut ix = *palette.entry(id.to_string()).or_insert(next) as usize;
+            // Palette indices are stored as varints.
+            loop {
+                let byte = (ix & 0b0111_1111) as u8;
+                ix >>= 7;
+                if ix == 0 {
+                    block_data.push(byte as i8);
+                    break;
+                }
+                block_data.push((byte | 0b1000_0000) as i8);
+            }
+
+            let entity = |id: &str, props: HashMap<String, Value>| SchemBlockEntity {
+                id: id.to_string(),
+                pos: vec![x as i32, y as i32, z as i32],
+                props,
+            };
+            match id.split('[').next().unwrap() {
+                "minecraft:comparator" => {
+                    let signal = self.signals.get(&pos).copied().unwrap_or(0);
+                    let props =
+                        HashMap::from([("OutputSignal".to_string(), Value::Byte(signal as i8))]);
+                    block_entities.push(entity("minecraft:comparator", props));
+                }
+                "minecraft:furnace" => {
+                    block_entities.push(entity("minecraft:furnace", HashMap::new()));
+                }
+                _ => {}
+            }
+        }
+
+        SchemFormat {
+            block_data,
+            block_entities,
+            data_version: 3218,
+            height: size.1 as i16,
+            length: size.2 as i16,
+            metadata: Metadata {
+                offset_x: 0,
+                offset_y: 0,
+                offset_z: 0,
+            },
+            offset: vec![offset.0 as i32, offset.1 as i32, offset.2 as i32],
+            palette_max: palette.len() as i32,
+            palette,
+            version: 2,
+            width: size.0 as i16,
+        }
+    }
+
+    pub fn build(&self) -> World {
+        self.build_with_prune_options(&PruneOptions::default())
+    }
+
+    /// Builds the world like `build`, running only the given pruning passes.
+    pub fn build_with_prune_options(&self, options: &PruneOptions) -> World {
+        let (mut cblocks, provenance) = cblock_graph(&self.schematic());
+        for idx in cblocks.node_indices().collect::<Vec<_>>() {
+            let Some(name) = provenance.position(idx).and_then(|p| self.names.get(&p)) else {
+                continue;
+            };
+            match &mut cblocks[idx] {
+                CBlock::Trigger(t) => t.name = name.clone(),
+                CBlock::Probe(p) => p.name = name.clone(),
+                _ => {}
+            }
+        }
+        World::from_cblock_graph(cblocks, provenance, options)
+    }
+}
@@ -0,0 +1,527 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::world::compiled::{repeater_levels, CompileError, CompiledGraph, NodeKind, NodeStates};
+use crate::world::World;
+use itertools::Itertools;
+use std::fmt::Write;
+
+/// Kind of Rust source produced by `generate`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// A module with a `State` struct, to be included using `include!`, e.g. from a build script.
+    Module,
+    /// The module together with `extern "C"` functions, to be built into a `cdylib`.
+    CDylib,
+}
+
+/// Generates Rust source that simulates the pruned graph of `world` from its current state.
+///
+/// Every tick is a single function of straight-line code, which updates the same nodes in the same
+/// way as `World::step`, and keeps the state of every node in flat arrays. Fails if the world
+/// cannot be compiled.
+pub fn generate(world: &World, target: Target) -> Result<String, CompileError> {
+    let graph = CompiledGraph::new(world)?;
+    let mut code = Generator {
+        graph: &graph,
+        tick_counter: world.tick_counter(),
+        out: String::new(),
+    };
+    code.header();
+    code.state();
+    code.tick();
+    code.io();
+    if target == Target::CDylib {
+        code.ffi();
+    }
+    Ok(code.out)
+}
+
+struct Generator<'a> {
+    graph: &'a CompiledGraph,
+    tick_counter: usize,
+    out: String,
+}
+
+/// Writes a formatted line to the generated source.
+macro_rules! emit {
+    ($g:expr) => {
+        writeln!($g.out).unwrap()
+    };
+    ($g:expr, $($arg:tt)*) => {
+        writeln!($g.out, $($arg)*).unwrap()
+    };
+}
+
+impl Generator<'_> {
+    /// Returns the unique names of the given nodes, in order of their first occurrence.
+    fn names(list: &[(String, u32)]) -> Vec<&str> {
+        list.iter().map(|(s, _)| s.as_str()).unique().collect()
+    }
+
+    /// Returns an expression for whether any of the given edges carries a signal.
+    fn any_input(&self, edges: impl Iterator<Item = (u32, u8)>) -> String {
+        let inputs = edges
+            .map(|(n, w)| format!("self.output[{n}] > {w}"))
+            .join(" || ");
+        if inputs.is_empty() {
+            "false".to_string()
+        } else {
+            inputs
+        }
+    }
+
+    fn header(&mut self) {
+        let n = self.graph.len();
+        emit!(self, "// Generated by redstone-simulator, do not edit.");
+        emit!(self);
+        emit!(self, "pub const NODES: usize = {n};");
+        let triggers = Self::names(&self.graph.triggers);
+        emit!(
+            self,
+            "pub const TRIGGERS: [&str; {}] = {triggers:?};",
+            triggers.len()
+        );
+        let probes = Self::names(&self.graph.probes);
+        emit!(
+            self,
+            "pub const PROBES: [&str; {}] = {probes:?};",
+            probes.len()
+        );
+        emit!(self);
+        emit!(
+            self,
+            "/// Returns whether a change of output from `prev` to `next` is seen through an edge with strength loss `w`."
+        );
+        emit!(self, "fn crosses(prev: u8, next: u8, w: u8) -> bool {{");
+        emit!(
+            self,
+            "    matches!((prev, next), (0, 15) | (15, 0)) || (prev.min(next)..prev.max(next)).contains(&w)"
+        );
+        emit!(self, "}}");
+        emit!(self);
+    }
+
+    fn state(&mut self) {
+        let NodeStates {
+            output,
+            next,
+            locking_signal: locking,
+            count,
+            on_inputs,
+            last_update,
+        } = self.graph.node_state_arrays();
+        let mut pending = vec![false; self.graph.len()];
+        for &n in &self.graph.pending {
+            pending[n as usize] = true;
+        }
+
+        emit!(self, "#[derive(Clone)]");
+        emit!(self, "pub struct State {{");
+        emit!(
+            self,
+            "    /// Output power of every node, or the signal of a probe."
+        );
+        emit!(self, "    pub output: [u8; NODES],");
+        emit!(
+            self,
+            "    /// Next signal of a comparator, or the next power of a repeater."
+        );
+        emit!(self, "    pub next: [u8; NODES],");
+        emit!(self, "    pub locking_signal: [bool; NODES],");
+        emit!(self, "    pub count: [u8; NODES],");
+        emit!(self, "    pub on_inputs: [u8; NODES],");
+        emit!(self, "    pub last_update: [usize; NODES],");
+        emit!(self, "    /// Nodes that are updated during the next tick.");
+        emit!(self, "    pub pending: [bool; NODES],");
+        emit!(self, "    pub tick_counter: usize,");
+        emit!(self, "}}");
+        emit!(self);
+        emit!(self, "impl Default for State {{");
+        emit!(self, "    fn default() -> Self {{");
+        emit!(self, "        State {{");
+        emit!(self, "            output: {output:?},");
+        emit!(self, "            next: {next:?},");
+        emit!(self, "            locking_signal: {locking:?},");
+        emit!(self, "            count: {count:?},");
+        emit!(self, "            on_inputs: {on_inputs:?},");
+        emit!(self, "            last_update: {last_update:?},");
+        emit!(self, "            pending: {pending:?},");
+        emit!(self, "            tick_counter: {},", self.tick_counter);
+        emit!(self, "        }}");
+        emit!(self, "    }}");
+        emit!(self, "}}");
+        emit!(self);
+    }
+
+    fn tick(&mut self) {
+        let graph = self.graph;
+        let (levels, cyclic) = repeater_levels(&graph.kinds, &graph.outgoing);
+
+        emit!(self, "impl State {{");
+        emit!(self, "    /// Simulates a single tick.");
+        emit!(
+            self,
+            "    #[allow(unused_mut, unused_variables, clippy::all)]"
+        );
+        emit!(self, "    pub fn tick(&mut self) {{");
+        emit!(
+            self,
+            "        let mut d = std::mem::replace(&mut self.pending, [false; NODES]);"
+        );
+        emit!(self, "        let mut u = [false; NODES];");
+
+        for (level, &is_cyclic) in cyclic.iter().enumerate() {
+            let members: Vec<u32> = (0..graph.len() as u32)
+                .filter(|&n| levels[n as usize] as usize == level)
+                .filter(|&n| !matches!(graph.kinds[n as usize], NodeKind::Trigger))
+                .collect();
+            emit!(self);
+            emit!(self, "        // Level {level}");
+            if is_cyclic {
+                emit!(self, "        loop {{");
+                emit!(self, "        let mut r = [false; NODES];");
+            }
+            for &n in &members {
+                self.update(n, &levels, is_cyclic);
+            }
+            if is_cyclic {
+                emit!(self, "        let mut again = false;");
+                for &n in &members {
+                    emit!(self, "        d[{n}] = r[{n}];");
+                    emit!(self, "        again |= r[{n}];");
+                }
+                emit!(self, "        if !again {{");
+                emit!(self, "            break;");
+                emit!(self, "        }}");
+                emit!(self, "        }}");
+            }
+        }
+
+        emit!(self);
+        emit!(self, "        // End-of-tick updates");
+        for n in 0..graph.len() as u32 {
+            self.late_update(n);
+        }
+        emit!(self);
+        emit!(self, "        self.tick_counter += 1;");
+        emit!(self, "    }}");
+    }
+
+    /// Writes the tick update of node `n`, like `Updatable::update`.
+    fn update(&mut self, n: u32, levels: &[u32], is_cyclic: bool) {
+        let graph = self.graph;
+        let i = n as usize;
+        let position = graph.positions[i]
+            .map(|p| format!(" at {p:?}"))
+            .unwrap_or_default();
+        emit!(self, "        // {:?}{position}", graph.kinds[i]);
+        emit!(self, "        if d[{n}] {{");
+        if is_cyclic {
+            emit!(self, "            d[{n}] = false;");
+        }
+
+        match graph.kinds[i] {
+            NodeKind::Trigger => {}
+            NodeKind::Probe => {
+                let on = self.any_input(graph.incoming_rear.edges(n));
+                emit!(
+                    self,
+                    "            self.output[{n}] = if {on} {{ 15 }} else {{ 0 }};"
+                );
+            }
+            NodeKind::SRepeater => {
+                let count = graph
+                    .incoming_rear
+                    .edges(n)
+                    .chain(graph.incoming_side.edges(n))
+                    .map(|(m, w)| format!("(self.output[{m}] > {w}) as u8"))
+                    .join(" + ");
+                let count = if count.is_empty() {
+                    "0".to_string()
+                } else {
+                    count
+                };
+                emit!(self, "            let on_inputs = {count};");
+                emit!(self, "            let prev = self.on_inputs[{n}];");
+                emit!(self, "            self.on_inputs[{n}] = on_inputs;");
+                emit!(self, "            if (prev > 0) != (on_inputs > 0) {{");
+                emit!(self, "                u[{n}] = true;");
+                emit!(self, "            }}");
+            }
+            NodeKind::Comparator { mode, entity_power } => {
+                let max = |edges: Vec<(u32, u8)>, start: u8| {
+                    edges.into_iter().fold(format!("{start}u8"), |acc, (m, w)| {
+                        format!("{acc}.max(self.output[{m}].saturating_sub({w}))")
+                    })
+                };
+                let rear = max(
+                    graph.incoming_rear.edges(n).collect(),
+                    entity_power.unwrap_or(0),
+                );
+                let side = max(graph.incoming_side.edges(n).collect(), 0);
+                emit!(self, "            let rear = {rear};");
+                emit!(self, "            let side = {side};");
+                match mode {
+                    ComparatorMode::Compare => emit!(
+                        self,
+                        "            let next = if side <= rear {{ rear }} else {{ 0 }};"
+                    ),
+                    ComparatorMode::Subtract => {
+                        emit!(self, "            let next = rear.saturating_sub(side);")
+                    }
+                }
+                emit!(self, "            self.next[{n}] = next;");
+                emit!(self, "            if self.output[{n}] != next {{");
+                emit!(self, "                u[{n}] = true;");
+                emit!(self, "            }}");
+            }
+            NodeKind::Repeater { delay } => {
+                let locked_now = self.any_input(graph.incoming_side.edges(n));
+                let s_new = self.any_input(graph.incoming_rear.edges(n));
+                let locked_next = graph
+                    .incoming_side
+                    .edges(n)
+                    .map(|(m, _)| match graph.kinds[m as usize] {
+                        NodeKind::Repeater { .. } => format!("self.locking_signal[{m}]"),
+                        NodeKind::Comparator { .. } => format!("self.output[{m}] > 0"),
+                        _ => unreachable!("Only repeaters and comparators can lock a repeater."),
+                    })
+                    .join(" || ");
+                let locked_next = if locked_next.is_empty() {
+                    "false".to_string()
+                } else {
+                    locked_next
+                };
+
+                emit!(self, "            if !({locked_now}) {{");
+                emit!(self, "            let s_new = {s_new};");
+                emit!(self, "            let locked_next_tick = {locked_next};");
+                emit!(
+                    self,
+                    "            if locked_next_tick == self.locking_signal[{n}] {{"
+                );
+                for (m, _) in graph.outgoing.edges(n) {
+                    if matches!(graph.kinds[m as usize], NodeKind::Repeater { .. }) {
+                        let list = if is_cyclic && levels[m as usize] == levels[i] {
+                            "r"
+                        } else {
+                            "d"
+                        };
+                        emit!(self, "                {list}[{m}] = true;");
+                    }
+                }
+                emit!(self, "            }}");
+                emit!(self, "            let powered = self.output[{n}] > 0;");
+                emit!(self, "            let mut next = self.next[{n}] > 0;");
+                emit!(self, "            if s_new && !next {{");
+                emit!(self, "                next = true;");
+                emit!(self, "                self.count[{n}] = 0;");
+                emit!(
+                    self,
+                    "            }} else if !s_new && next && self.count[{n}] == 0 {{"
+                );
+                emit!(self, "                next = false;");
+                emit!(self, "            }}");
+                emit!(self, "            self.next[{n}] = next as u8;");
+                emit!(
+                    self,
+                    "            self.locking_signal[{n}] = if locked_next_tick {{ powered }} else if self.count[{n}] + 1 == {delay} {{ next }} else {{ powered }};"
+                );
+                emit!(self, "            if powered != next {{");
+                emit!(self, "                u[{n}] = true;");
+                emit!(self, "            }}");
+                emit!(self, "            }}");
+            }
+        }
+        emit!(self, "        }}");
+    }
+
+    /// Writes the end-of-tick update of node `n`, like `Updatable::late_update`.
+    fn late_update(&mut self, n: u32) {
+        let graph = self.graph;
+        let push_all = |g: &mut Self, indent: &str| {
+            for (m, _) in graph.outgoing.edges(n) {
+                emit!(g, "{indent}self.pending[{m}] = true;");
+            }
+        };
+
+        match graph.kinds[n as usize] {
+            NodeKind::Trigger | NodeKind::Probe => {}
+            NodeKind::SRepeater => {
+                emit!(self, "        if u[{n}] {{");
+                emit!(
+                    self,
+                    "            self.output[{n}] = 15 - self.output[{n}];"
+                );
+                push_all(self, "            ");
+                emit!(self, "        }}");
+            }
+            NodeKind::Comparator { .. } => {
+                emit!(self, "        if u[{n}] {{");
+                emit!(self, "            let prev = self.output[{n}];");
+                emit!(self, "            let next = self.next[{n}];");
+                emit!(self, "            self.output[{n}] = next;");
+                for (m, w) in graph.outgoing.edges(n) {
+                    emit!(self, "            if crosses(prev, next, {w}) {{");
+                    emit!(self, "                self.pending[{m}] = true;");
+                    emit!(self, "            }}");
+                }
+                emit!(self, "        }}");
+            }
+            NodeKind::Repeater { delay } => {
+                emit!(
+                    self,
+                    "        if u[{n}] && self.last_update[{n}] != self.tick_counter {{"
+                );
+                emit!(
+                    self,
+                    "            self.last_update[{n}] = self.tick_counter;"
+                );
+                emit!(self, "            self.pending[{n}] = true;");
+                emit!(self, "            self.count[{n}] += 1;");
+                emit!(self, "            if self.count[{n}] == {delay} {{");
+                emit!(self, "                self.count[{n}] = 0;");
+                emit!(
+                    self,
+                    "                self.output[{n}] = if self.locking_signal[{n}] {{ 15 }} else {{ 0 }};"
+                );
+                push_all(self, "                ");
+                emit!(self, "            }}");
+                emit!(self, "        }}");
+            }
+        }
+    }
+
+    fn io(&mut self) {
+        let graph = self.graph;
+
+        emit!(self);
+        emit!(
+            self,
+            "    /// Powers or unpowers every trigger with the given name until it is set again."
+        );
+        emit!(self, "    /// Returns `false` if there is no such trigger.");
+        emit!(
+            self,
+            "    pub fn set_trigger(&mut self, name: &str, powered: bool) -> bool {{"
+        );
+        emit!(
+            self,
+            "        let output = if powered {{ 15 }} else {{ 0 }};"
+        );
+        emit!(self, "        match name {{");
+        for name in Self::names(&graph.triggers) {
+            emit!(self, "            {name:?} => {{");
+            for (_, n) in graph.triggers.iter().filter(|(s, _)| s == name) {
+                emit!(self, "                if self.output[{n}] != output {{");
+                emit!(self, "                    self.output[{n}] = output;");
+                for (m, _) in graph.outgoing.edges(*n) {
+                    emit!(self, "                    self.pending[{m}] = true;");
+                }
+                emit!(self, "                }}");
+            }
+            emit!(self, "                true");
+            emit!(self, "            }}");
+        }
+        emit!(self, "            _ => false,");
+        emit!(self, "        }}");
+        emit!(self, "    }}");
+        emit!(self);
+        emit!(self, "    /// Powers all triggers for a single tick.");
+        emit!(self, "    pub fn step_with_trigger(&mut self) {{");
+        emit!(self, "        for name in TRIGGERS {{");
+        emit!(self, "            self.set_trigger(name, true);");
+        emit!(self, "        }}");
+        emit!(self, "        self.tick();");
+        emit!(self, "        for name in TRIGGERS {{");
+        emit!(self, "            self.set_trigger(name, false);");
+        emit!(self, "        }}");
+        emit!(self, "    }}");
+        emit!(self);
+        emit!(
+            self,
+            "    /// Returns whether the probe is currently powered."
+        );
+        emit!(
+            self,
+            "    pub fn get_probe(&self, name: &str) -> Option<bool> {{"
+        );
+        emit!(self, "        match name {{");
+        for (name, n) in graph.probes.iter().unique_by(|(s, _)| s) {
+            emit!(self, "            {name:?} => Some(self.output[{n}] > 0),");
+        }
+        emit!(self, "            _ => None,");
+        emit!(self, "        }}");
+        emit!(self, "    }}");
+        emit!(self, "}}");
+    }
+
+    fn ffi(&mut self) {
+        emit!(self);
+        emit!(self, "#[no_mangle]");
+        emit!(self, "pub extern \"C\" fn redstone_new() -> *mut State {{");
+        emit!(self, "    Box::into_raw(Box::default())");
+        emit!(self, "}}");
+        emit!(self);
+        emit!(self, "/// # Safety");
+        emit!(
+            self,
+            "/// `state` must be returned by `redstone_new`, and not be used afterwards."
+        );
+        emit!(self, "#[no_mangle]");
+        emit!(
+            self,
+            "pub unsafe extern \"C\" fn redstone_free(state: *mut State) {{"
+        );
+        emit!(self, "    drop(Box::from_raw(state));");
+        emit!(self, "}}");
+        emit!(self);
+        emit!(self, "/// # Safety");
+        emit!(self, "/// `state` must be returned by `redstone_new`.");
+        emit!(self, "#[no_mangle]");
+        emit!(
+            self,
+            "pub unsafe extern \"C\" fn redstone_tick(state: *mut State) {{"
+        );
+        emit!(self, "    (*state).tick();");
+        emit!(self, "}}");
+        emit!(self);
+        emit!(
+            self,
+            "/// Sets the trigger with the given index in `TRIGGERS`."
+        );
+        emit!(self, "///");
+        emit!(self, "/// # Safety");
+        emit!(self, "/// `state` must be returned by `redstone_new`.");
+        emit!(self, "#[no_mangle]");
+        emit!(
+            self,
+            "pub unsafe extern \"C\" fn redstone_set_trigger(state: *mut State, trigger: usize, powered: bool) -> bool {{"
+        );
+        emit!(self, "    match TRIGGERS.get(trigger) {{");
+        emit!(
+            self,
+            "        Some(name) => (*state).set_trigger(name, powered),"
+        );
+        emit!(self, "        None => false,");
+        emit!(self, "    }}");
+        emit!(self, "}}");
+        emit!(self);
+        emit!(
+            self,
+            "/// Returns the probe with the given index in `PROBES`."
+        );
+        emit!(self, "///");
+        emit!(self, "/// # Safety");
+        emit!(self, "/// `state` must be returned by `redstone_new`.");
+        emit!(self, "#[no_mangle]");
+        emit!(
+            self,
+            "pub unsafe extern \"C\" fn redstone_get_probe(state: *const State, probe: usize) -> bool {{"
+        );
+        emit!(
+            self,
+            "    PROBES.get(probe).and_then(|name| (*state).get_probe(name)) == Some(true)"
+        );
+        emit!(self, "}}");
+    }
+}
@@ -0,0 +1,425 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::{Block, BlockState, CBlock, RuntimeState};
+use crate::world::graph::{GEdge, GNode};
+use crate::world::World;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+/// Kind and parameters of a node in a `CompiledGraph`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Trigger,
+    Probe,
+    SRepeater,
+    Repeater {
+        delay: u8,
+    },
+    Comparator {
+        mode: ComparatorMode,
+        entity_power: Option<u8>,
+    },
+}
+
+/// Node of a `World` that has no `NodeKind`, with its position in the schematic if known.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    /// Delay lines keep a queue of the pulses of their repeaters, which does not fit in the fixed
+    /// fields of a node.
+    DelayLine(Option<(isize, isize, isize)>),
+    /// Buffers keep the history of their input over more ticks than a node has fields for.
+    Buffer(Option<(isize, isize, isize)>),
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (what, position) = match self {
+            CompileError::DelayLine(p) => ("delay line", p),
+            CompileError::Buffer(p) => ("buffer", p),
+        };
+        match position {
+            Some((x, y, z)) => write!(f, "{what} at {x},{y},{z} can not be compiled")?,
+            None => write!(f, "{what} can not be compiled")?,
+        }
+        write!(f, ", build the world without pruning {what}s")
+    }
+}
+
+impl error::Error for CompileError {}
+
+/// Edges of a graph in compressed sparse row form, the edges of node `i` are found at
+/// `offsets[i]..offsets[i + 1]`.
+#[derive(Clone, Debug, Default)]
+pub struct Csr {
+    pub offsets: Vec<u32>,
+    /// Node on the other end of each edge.
+    pub nodes: Vec<u32>,
+    /// Strength loss of each edge.
+    pub weights: Vec<u8>,
+}
+
+impl Csr {
+    fn new<'a>(
+        edges: impl Iterator<Item = &'a [GEdge<Block, u8>]>,
+        index: impl Fn(&GNode<Block, u8>) -> u32,
+    ) -> Self {
+        let mut csr = Csr {
+            offsets: vec![0],
+            ..Default::default()
+        };
+        for list in edges {
+            for e in list {
+                csr.nodes.push(index(e.node));
+                csr.weights.push(e.weight);
+            }
+            csr.offsets.push(csr.nodes.len() as u32);
+        }
+        csr
+    }
+
+    /// Returns the other end and strength loss of every edge of the given node.
+    pub fn edges(&self, node: u32) -> impl Iterator<Item = (u32, u8)> + '_ {
+        let range = self.offsets[node as usize] as usize..self.offsets[node as usize + 1] as usize;
+        self.nodes[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.weights[range].iter().copied())
+    }
+}
+
+/// Flat copy of the runtime graph of a `World`, with nodes indexed by `u32` in the order of
+/// `World::blocks`. Used as the input of alternative simulation backends.
+#[derive(Clone, Debug)]
+pub struct CompiledGraph {
+    pub kinds: Vec<NodeKind>,
+
+    /// Runtime state of every node at the time the graph was compiled.
+    pub states: Vec<BlockState>,
+
+    pub outgoing: Csr,
+    pub incoming_rear: Csr,
+    pub incoming_side: Csr,
+
+    /// Names of the triggers with their nodes.
+    pub triggers: Vec<(String, u32)>,
+
+    /// Names of the probes with their nodes, sorted by name.
+    pub probes: Vec<(String, u32)>,
+
+    /// Position of every node in the schematic, if known.
+    pub positions: Vec<Option<(isize, isize, isize)>>,
+
+    /// Nodes that are updated during the next tick, sorted and without duplicates.
+    pub pending: Vec<u32>,
+}
+
+impl CompiledGraph {
+    /// Compiles the runtime graph of `world`. Fails if it contains delay lines or buffers.
+    pub fn new(world: &World) -> Result<Self, CompileError> {
+        let nodes = &world.blocks.nodes;
+        let positions: HashMap<*const GNode<Block, u8>, u32> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (n as *const _, i as u32))
+            .collect();
+        let index = |n: &GNode<Block, u8>| positions[&(n as *const _)];
+
+        let mut triggers = Vec::new();
+        let mut probes = Vec::new();
+        let kinds = nodes
+            .iter()
+            .zip(&world.blocks.indices)
+            .enumerate()
+            .map(|(i, (n, &idx))| match (&n.weight, &world.cblocks[idx]) {
+                (Block::Redstone(_), CBlock::Trigger(t)) => {
+                    triggers.push((t.name.clone(), i as u32));
+                    Ok(NodeKind::Trigger)
+                }
+                (Block::Redstone(_), CBlock::Probe(p)) => {
+                    probes.push((p.name.clone(), i as u32));
+                    Ok(NodeKind::Probe)
+                }
+                (Block::Redstone(_), _) => unreachable!("Dust should have been pruned."),
+                (Block::SRepeater(_), _) => Ok(NodeKind::SRepeater),
+                (Block::Repeater(r), _) => Ok(NodeKind::Repeater { delay: r.delay() }),
+                (Block::Comparator(c), _) => Ok(NodeKind::Comparator {
+                    mode: c.mode(),
+                    entity_power: c.entity_power(),
+                }),
+                // Behaves exactly like a comparator, since its inputs are binary.
+                (Block::BinaryComparator(c), _) => Ok(NodeKind::Comparator {
+                    mode: c.mode(),
+                    entity_power: None,
+                }),
+                (Block::DelayLine(_), _) => {
+                    Err(CompileError::DelayLine(world.provenance.position(idx)))
+                }
+                (Block::Buffer(_), _) => Err(CompileError::Buffer(world.provenance.position(idx))),
+            })
+            .collect::<Result<_, _>>()?;
+        probes.sort();
+
+        let mut pending = world
+            .updatable
+            .iter()
+            .chain(&world.tick_updatable.up)
+            .chain(&world.tick_updatable.down)
+            .map(|&n| index(n))
+            .collect::<Vec<_>>();
+        pending.sort_unstable();
+        pending.dedup();
+
+        Ok(CompiledGraph {
+            kinds,
+            states: nodes.iter().map(|n| n.weight.state()).collect(),
+            outgoing: Csr::new(nodes.iter().map(|n| n.outgoing), index),
+            incoming_rear: Csr::new(nodes.iter().map(|n| n.incoming_rear), index),
+            incoming_side: Csr::new(nodes.iter().map(|n| n.incoming_side), index),
+            triggers,
+            probes,
+            positions: world
+                .blocks
+                .indices
+                .iter()
+                .map(|&idx| world.provenance.position(idx))
+                .collect(),
+            pending,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Returns the runtime fields of every node.
+    pub fn node_states(&self) -> impl Iterator<Item = NodeState> + '_ {
+        self.states.iter().map(NodeState::from)
+    }
+
+    /// Returns the runtime fields of every node in separate arrays.
+    pub fn node_state_arrays(&self) -> NodeStates {
+        let mut arrays = NodeStates::default();
+        for state in self.node_states() {
+            arrays.push(state);
+        }
+        arrays
+    }
+}
+
+/// Runtime fields of a node, shared by every kind of node. Fields that a kind does not use are 0,
+/// except for `last_update`, which is `usize::MAX`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NodeState {
+    /// Output power, or the signal of a probe.
+    pub output: u8,
+    /// Next signal of a comparator, or the next power of a repeater.
+    pub next: u8,
+    pub locking_signal: bool,
+    pub count: u8,
+    pub on_inputs: u8,
+    pub last_update: usize,
+}
+
+impl Default for NodeState {
+    fn default() -> Self {
+        NodeState {
+            output: 0,
+            next: 0,
+            locking_signal: false,
+            count: 0,
+            on_inputs: 0,
+            last_update: usize::MAX,
+        }
+    }
+}
+
+impl From<&BlockState> for NodeState {
+    fn from(state: &BlockState) -> Self {
+        match *state {
+            BlockState::Redstone { signal } => NodeState {
+                output: signal as u8 * 15,
+                ..Default::default()
+            },
+            BlockState::Repeater {
+                powered,
+                next_powered,
+                locking_signal,
+                count,
+                last_update,
+            } => NodeState {
+                output: powered as u8 * 15,
+                next: next_powered as u8,
+                locking_signal,
+                count,
+                last_update,
+                ..Default::default()
+            },
+            BlockState::Comparator {
+                signal,
+                next_signal,
+            } => NodeState {
+                output: signal,
+                next: next_signal,
+                ..Default::default()
+            },
+            BlockState::SRepeater { powered, on_inputs } => NodeState {
+                output: powered as u8 * 15,
+                on_inputs,
+                ..Default::default()
+            },
+            BlockState::DelayLine { .. } | BlockState::Buffer { .. } => {
+                unreachable!("Delay lines and buffers are not compiled.")
+            }
+        }
+    }
+}
+
+impl NodeState {
+    /// Returns the runtime state of a node of the given kind with these fields.
+    pub fn block_state(&self, kind: NodeKind) -> BlockState {
+        match kind {
+            NodeKind::Trigger | NodeKind::Probe => BlockState::Redstone {
+                signal: self.output > 0,
+            },
+            NodeKind::SRepeater => BlockState::SRepeater {
+                powered: self.output > 0,
+                on_inputs: self.on_inputs,
+            },
+            NodeKind::Repeater { .. } => BlockState::Repeater {
+                powered: self.output > 0,
+                next_powered: self.next > 0,
+                locking_signal: self.locking_signal,
+                count: self.count,
+                last_update: self.last_update,
+            },
+            NodeKind::Comparator { .. } => BlockState::Comparator {
+                signal: self.output,
+                next_signal: self.next,
+            },
+        }
+    }
+}
+
+/// Fields of every node in separate arrays indexed by `u32`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeStates {
+    /// Output power of every node, or the signal of a probe.
+    pub output: Vec<u8>,
+    /// Next signal of a comparator, or the next power of a repeater.
+    pub next: Vec<u8>,
+    pub locking_signal: Vec<bool>,
+    pub count: Vec<u8>,
+    pub on_inputs: Vec<u8>,
+    pub last_update: Vec<usize>,
+}
+
+impl NodeStates {
+    /// Returns the fields of node `i`.
+    pub fn get(&self, i: usize) -> NodeState {
+        NodeState {
+            output: self.output[i],
+            next: self.next[i],
+            locking_signal: self.locking_signal[i],
+            count: self.count[i],
+            on_inputs: self.on_inputs[i],
+            last_update: self.last_update[i],
+        }
+    }
+
+    fn push(&mut self, state: NodeState) {
+        self.output.push(state.output);
+        self.next.push(state.next);
+        self.locking_signal.push(state.locking_signal);
+        self.count.push(state.count);
+        self.on_inputs.push(state.on_inputs);
+        self.last_update.push(state.last_update);
+    }
+}
+
+/// Orders the repeaters by the chains of repeaters that push them within a tick. Returns the level
+/// of every node, and whether every level contains a cycle of repeaters.
+pub fn repeater_levels(kinds: &[NodeKind], outgoing: &Csr) -> (Vec<u32>, Vec<bool>) {
+    let is_repeater = |n: u32| matches!(kinds[n as usize], NodeKind::Repeater { .. });
+    let mut successors = vec![Vec::new(); kinds.len()];
+    let mut predecessors = vec![Vec::new(); kinds.len()];
+    for n in (0..kinds.len() as u32).filter(|&n| is_repeater(n)) {
+        for (m, _) in outgoing.edges(n).filter(|&(m, _)| is_repeater(m)) {
+            successors[n as usize].push(m);
+            predecessors[m as usize].push(n);
+        }
+    }
+
+    // Kosaraju's algorithm, which finds the strongly connected components in topological order.
+    let mut visited = vec![false; kinds.len()];
+    let mut finished = Vec::with_capacity(kinds.len());
+    for start in 0..kinds.len() as u32 {
+        if visited[start as usize] {
+            continue;
+        }
+        visited[start as usize] = true;
+        let mut stack = vec![(start, 0)];
+        while let Some((n, i)) = stack.pop() {
+            if let Some(&m) = successors[n as usize].get(i) {
+                stack.push((n, i + 1));
+                if !visited[m as usize] {
+                    visited[m as usize] = true;
+                    stack.push((m, 0));
+                }
+            } else {
+                finished.push(n);
+            }
+        }
+    }
+
+    let mut component = vec![u32::MAX; kinds.len()];
+    let mut components: Vec<Vec<u32>> = Vec::new();
+    for &start in finished.iter().rev() {
+        if component[start as usize] != u32::MAX {
+            continue;
+        }
+        let c = components.len() as u32;
+        component[start as usize] = c;
+        let mut members = vec![start];
+        let mut stack = vec![start];
+        while let Some(n) = stack.pop() {
+            for &m in &predecessors[n as usize] {
+                if component[m as usize] == u32::MAX {
+                    component[m as usize] = c;
+                    members.push(m);
+                    stack.push(m);
+                }
+            }
+        }
+        components.push(members);
+    }
+
+    let mut component_levels = vec![0; components.len()];
+    let mut cyclic = vec![false];
+    for (c, members) in components.iter().enumerate() {
+        let level = component_levels[c];
+        let is_cycle = members.len() > 1 || successors[members[0] as usize].contains(&members[0]);
+        if cyclic.len() <= level as usize {
+            cyclic.resize(level as usize + 1, false);
+        }
+        cyclic[level as usize] |= is_cycle;
+
+        for &n in members {
+            for &m in &successors[n as usize] {
+                let d = component[m as usize] as usize;
+                if d != c {
+                    component_levels[d] = component_levels[d].max(level + 1);
+                }
+            }
+        }
+    }
+
+    let levels = component
+        .iter()
+        .map(|&c| component_levels[c as usize])
+        .collect();
+    (levels, cyclic)
+}
@@ -1,12 +1,12 @@
-use crate::blocks::{Block, CBlock};
+use crate::blocks::{Block, CBlock, OutputPower};
 use crate::world::data::{neighbours_and_facings, TileMap, WorldData};
 use crate::world::graph::GNode;
-use crate::world::prune::prune_graph;
+use crate::world::provenance::Provenance;
+use crate::world::prune::{prune_graph, PruneOptions};
 use crate::world::schematic::SchemFormat;
 use crate::world::{BlockGraph, CBlockGraph, TickUpdatableLists, UpdatableList, World};
-use itertools::iproduct;
+use itertools::{iproduct, Itertools};
 use nbt::from_gzip_reader;
-use petgraph::prelude::NodeIndex;
 use std::collections::HashMap;
 use std::fs::File;
 
@@ -16,22 +16,25 @@ impl From<File> for World {
     }
 }
 
+impl From<SchemFormat> for World {
+    fn from(format: SchemFormat) -> Self {
+        World::from_format_with_prune_options(format, &PruneOptions::default())
+    }
+}
+
+type Triggers = Vec<(String, &'static GNode<Block, u8>)>;
+type Probes = HashMap<String, &'static GNode<Block, u8>>;
+
 impl World {
-    fn cblock_to_block(
-        cblocks: &CBlockGraph,
-    ) -> (
-        BlockGraph,
-        Vec<&'static GNode<Block, u8>>,
-        HashMap<String, &'static GNode<Block, u8>>,
-    ) {
+    fn cblock_to_block(cblocks: &CBlockGraph) -> (BlockGraph, Triggers, Probes) {
         let mut triggers = Vec::new();
         let mut probes = HashMap::new();
-        let blocks = BlockGraph::from_petgraph(&cblocks, |cblock, block_ref| match cblock {
+        let blocks = BlockGraph::from_petgraph(cblocks, |cblock, block_ref| match cblock {
             CBlock::Probe(p) => {
                 probes.insert(p.name.clone(), block_ref);
             }
-            CBlock::Trigger(_) => {
-                triggers.push(block_ref);
+            CBlock::Trigger(t) => {
+                triggers.push((t.name.clone(), block_ref));
             }
             _ => {}
         });
@@ -40,67 +43,97 @@ impl World {
     }
 }
 
-impl From<SchemFormat> for World {
-    fn from(format: SchemFormat) -> Self {
-        let tile_map: TileMap = format
-            .block_entities
-            .iter()
-            .map(|b| ((b.pos[0] as usize, b.pos[1] as usize, b.pos[2] as usize), b))
-            .collect();
-
-        let world = WorldData::from_format(&format, &tile_map);
-
-        let height = format.height as usize;
-        let length = format.length as usize;
-        let width = format.width as usize;
-
-        let mut cblocks = CBlockGraph::new();
-        let mut indexes = vec![vec![vec![vec![]; length]; height]; width];
-
-        let mut cblock_positions: HashMap<NodeIndex, (isize, isize, isize)> = HashMap::new();
-
-        // Construct nodes.
-        for (x, y, z) in iproduct!(0..width, 0..height, 0..length) {
-            for block in &world[(x, y, z)] {
-                let idx = cblocks.add_node(block.clone());
-                indexes[x][y][z].push(idx);
-                cblock_positions.insert(
-                    idx,
-                    (
-                        x as isize + format.offset[0] as isize,
-                        y as isize + format.offset[1] as isize,
-                        z as isize + format.offset[2] as isize,
-                    ),
-                );
-            }
+/// Constructs the unpruned graph of the blocks in a schematic, with the position of every node.
+pub(crate) fn cblock_graph(format: &SchemFormat) -> (CBlockGraph, Provenance) {
+    let tile_map: TileMap = format
+        .block_entities
+        .iter()
+        .map(|b| ((b.pos[0] as usize, b.pos[1] as usize, b.pos[2] as usize), b))
+        .collect();
+
+    let world = WorldData::from_format(format, &tile_map);
+
+    let height = format.height as usize;
+    let length = format.length as usize;
+    let width = format.width as usize;
+
+    let mut cblocks = CBlockGraph::new();
+    let mut indexes = vec![vec![vec![vec![]; length]; height]; width];
+
+    let mut provenance = Provenance::default();
+
+    // Construct nodes.
+    for (x, y, z) in iproduct!(0..width, 0..height, 0..length) {
+        for block in &world[(x, y, z)] {
+            let idx = cblocks.add_node(block.clone());
+            indexes[x][y][z].push(idx);
+            provenance.nodes.insert(
+                idx,
+                vec![(
+                    x as isize + format.offset[0] as isize,
+                    y as isize + format.offset[1] as isize,
+                    z as isize + format.offset[2] as isize,
+                )],
+            );
         }
+    }
 
-        // Construct edges.
-        for (x, y, z) in iproduct!(0..width, 0..height, 0..length) {
-            for (block, &idx) in world[(x, y, z)].iter().zip(indexes[x][y][z].iter()) {
-                for (np, f) in neighbours_and_facings((x, y, z)) {
-                    let n_idxs = indexes
-                        .get(np.0)
-                        .and_then(|l| l.get(np.1).and_then(|l| l.get(np.2)))
-                        .map(|v| v.iter())
-                        .into_iter()
-                        .flatten();
-
-                    for (n_block, &n_idx) in world[np].iter().zip(n_idxs) {
-                        if let Some(edge) = block.get_edge(n_block, f) {
-                            cblocks.add_edge(idx, n_idx, edge);
-                        }
+    // Construct edges.
+    for (x, y, z) in iproduct!(0..width, 0..height, 0..length) {
+        for (block, &idx) in world[(x, y, z)].iter().zip(indexes[x][y][z].iter()) {
+            for (np, f) in neighbours_and_facings((x, y, z)) {
+                let n_idxs = indexes
+                    .get(np.0)
+                    .and_then(|l| l.get(np.1).and_then(|l| l.get(np.2)))
+                    .map(|v| v.iter())
+                    .into_iter()
+                    .flatten();
+
+                for (n_block, &n_idx) in world[np].iter().zip(n_idxs) {
+                    if let Some(edge) = block.get_edge(n_block, f) {
+                        cblocks.add_edge(idx, n_idx, edge);
                     }
                 }
+            }
 
-                // construct vertical edges for redstone
-                if let CBlock::Redstone(v) = block {
-                    v.add_vertical_edges((x, y, z), &mut cblocks, &world, &indexes);
-                }
+            // construct vertical edges for redstone
+            if let CBlock::Redstone(v) = block {
+                v.add_vertical_edges((x, y, z), &mut cblocks, &world, &indexes);
             }
         }
+    }
 
-        prune_graph(&mut cblocks);
+    (cblocks, provenance)
+}
+
+impl World {
+    /// Loads a schematic file like `World::from(File)`, running only the given pruning passes.
+    pub fn from_file_with_prune_options(file: File, options: &PruneOptions) -> World {
+        World::from_format_with_prune_options(
+            from_gzip_reader::<File, SchemFormat>(file).unwrap(),
+            options,
+        )
+    }
+
+    /// Constructs a world from a schematic like `World::from(SchemFormat)`, running only the given
+    /// pruning passes.
+    pub fn from_format_with_prune_options(format: SchemFormat, options: &PruneOptions) -> World {
+        let (cblocks, provenance) = cblock_graph(&format);
+        World::from_cblock_graph(cblocks, provenance, options)
+    }
+
+    /// Constructs a world from an unpruned graph that was not read from a schematic, running only
+    /// the given pruning passes. The blocks of the graph must have consistent initial states.
+    pub fn from_cblock_graph(
+        mut cblocks: CBlockGraph,
+        mut provenance: Provenance,
+        options: &PruneOptions,
+    ) -> World {
+        let prune_stats = prune_graph(&mut cblocks, &mut provenance, options);
+        let cblock_positions = cblocks
+            .node_indices()
+            .filter_map(|idx| Some((idx, provenance.position(idx)?)))
+            .collect();
 
         // CBlock graph to Block graph
         let (blocks, triggers, probes) = World::cblock_to_block(&cblocks);
@@ -108,6 +141,7 @@ impl From<SchemFormat> for World {
         let mut world = World {
             cblocks,
             cblock_positions,
+            provenance,
             blocks,
             triggers,
             probes,
@@ -117,12 +151,23 @@ impl From<SchemFormat> for World {
                 up: UpdatableList::new(),
             },
             tick_counter: 0,
+            probe_values: Vec::new(),
+            probes_updated: false,
+            prune_stats,
         };
 
         // Update probes for initial state.
         world.tick_updatable.down = world.probes.values().cloned().collect();
         world.step();
 
+        world.probe_values = world
+            .probes
+            .iter()
+            .map(|(s, &i)| (s.clone(), i, i.weight.output_power() > 0))
+            .sorted_by(|a, b| a.0.cmp(&b.0))
+            .collect();
+        world.probes_updated = false;
+
         world
     }
 }
@@ -56,6 +56,7 @@ impl WorldData {
                     match &mut b {
                         CBlock::Comparator(v) => v.update_from_tile((x, y, z), tile_map),
                         CBlock::Probe(v) => v.update_from_tile((x, y, z), tile_map),
+                        CBlock::Trigger(v) => v.update_from_tile((x, y, z), tile_map),
                         _ => {}
                     }
                     b
@@ -0,0 +1,338 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::{Block, BlockState};
+use crate::world::compiled::{CompileError, CompiledGraph, Csr, NodeKind, NodeStates};
+use crate::world::graph::GNode;
+use crate::world::World;
+use std::collections::HashMap;
+
+/// A `World` with the runtime state of every node in contiguous arrays indexed by `u32`, and the
+/// edges in compressed sparse row form, instead of linked `GNode`s.
+///
+/// Nodes are updated in exactly the same order as by `World::step`, so both stay identical.
+pub struct FlatWorld {
+    kinds: Vec<NodeKind>,
+    outgoing: Edges,
+    incoming_rear: Edges,
+    incoming_side: Edges,
+
+    /// Outgoing edges to repeaters only, which repeaters update within the same tick.
+    outgoing_repeaters: Edges,
+
+    /// Runtime fields of every node.
+    state: NodeStates,
+
+    triggers: Vec<(String, u32)>,
+    probes: Vec<(String, u32)>,
+
+    /// Queue that holds nodes that require an end-of-tick update.
+    updatable: Vec<u32>,
+
+    /// Queues that hold nodes that require an intra-tick update.
+    up: Vec<u32>,
+    down: Vec<u32>,
+
+    /// Global tick counter.
+    tick_counter: usize,
+}
+
+/// Edges of every node like `Csr`, but with the other end and strength loss of an edge side by side.
+struct Edges {
+    offsets: Vec<u32>,
+    edges: Vec<(u32, u8)>,
+}
+
+impl Edges {
+    fn new(csr: &Csr, keep: impl Fn(u32) -> bool) -> Self {
+        let mut edges = Edges {
+            offsets: vec![0],
+            edges: Vec::with_capacity(csr.nodes.len()),
+        };
+        for n in 0..csr.offsets.len() as u32 - 1 {
+            edges.edges.extend(csr.edges(n).filter(|&(m, _)| keep(m)));
+            edges.offsets.push(edges.edges.len() as u32);
+        }
+        edges
+    }
+
+    /// Returns the other end and strength loss of every edge of the given node.
+    fn get(&self, n: u32) -> &[(u32, u8)] {
+        &self.edges[self.offsets[n as usize] as usize..self.offsets[n as usize + 1] as usize]
+    }
+}
+
+impl FlatWorld {
+    /// Creates a `FlatWorld` in the current state of `world`. Fails if the world cannot be compiled.
+    pub fn new(world: &World) -> Result<Self, CompileError> {
+        let graph = CompiledGraph::new(world)?;
+
+        let kinds = &graph.kinds;
+        let outgoing_repeaters = Edges::new(&graph.outgoing, |m| {
+            matches!(kinds[m as usize], NodeKind::Repeater { .. })
+        });
+
+        // The order of the queues decides the order of the updates, so it is kept as is.
+        let positions: HashMap<*const GNode<Block, u8>, u32> = world
+            .blocks
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (n as *const _, i as u32))
+            .collect();
+        let to_indices = |list: &[&'static GNode<Block, u8>]| {
+            list.iter().map(|&n| positions[&(n as *const _)]).collect()
+        };
+
+        Ok(FlatWorld {
+            outgoing: Edges::new(&graph.outgoing, |_| true),
+            incoming_rear: Edges::new(&graph.incoming_rear, |_| true),
+            incoming_side: Edges::new(&graph.incoming_side, |_| true),
+            outgoing_repeaters,
+            state: graph.node_state_arrays(),
+            kinds: graph.kinds,
+            triggers: graph.triggers,
+            probes: graph.probes,
+            updatable: to_indices(&world.updatable),
+            up: to_indices(&world.tick_updatable.up),
+            down: to_indices(&world.tick_updatable.down),
+            tick_counter: world.tick_counter(),
+        })
+    }
+
+    /// Returns whether the probe is currently powered.
+    pub fn get_probe(&self, name: &str) -> Option<bool> {
+        self.probes
+            .iter()
+            .find(|(s, _)| s == name)
+            .map(|(_, n)| self.state.output[*n as usize] > 0)
+    }
+
+    /// Returns `HashMap` from the names of probes to whether they are currently powered.
+    pub fn get_probes(&self) -> HashMap<&str, bool> {
+        self.probes
+            .iter()
+            .map(|(s, n)| (s.as_str(), self.state.output[*n as usize] > 0))
+            .collect()
+    }
+
+    /// Powers or unpowers every trigger with the given name until it is set again.
+    /// Returns `false` if there is no such trigger.
+    pub fn set_trigger(&mut self, name: &str, powered: bool) -> bool {
+        let mut found = false;
+        for (_, n) in self.triggers.iter().filter(|(s, _)| s == name) {
+            found = true;
+            let output = if powered { 15 } else { 0 };
+            if self.state.output[*n as usize] == output {
+                continue;
+            }
+            self.state.output[*n as usize] = output;
+
+            let neighbours = self.outgoing.get(*n).iter().map(|&(m, _)| m);
+            if powered {
+                self.up.extend(neighbours);
+            } else {
+                self.down.extend(neighbours);
+            }
+        }
+        found
+    }
+
+    pub fn step_with_trigger(&mut self) {
+        // put redstone power on triggers
+        for i in 0..self.triggers.len() {
+            let n = self.triggers[i].1;
+            self.state.output[n as usize] ^= 15;
+            self.up.extend(self.outgoing.get(n).iter().map(|&(m, _)| m));
+        }
+
+        self.step();
+
+        // take redstone power off triggers
+        for i in 0..self.triggers.len() {
+            let n = self.triggers[i].1;
+            self.state.output[n as usize] ^= 15;
+            self.down
+                .extend(self.outgoing.get(n).iter().map(|&(m, _)| m));
+        }
+    }
+
+    /// Returns the number of ticks simulated so far.
+    pub fn tick_counter(&self) -> usize {
+        self.tick_counter
+    }
+
+    /// Returns the runtime state of every node, in the order of `World::blocks`.
+    pub fn states(&self) -> Vec<BlockState> {
+        (0..self.kinds.len())
+            .map(|i| self.state.get(i).block_state(self.kinds[i]))
+            .collect()
+    }
+
+    pub fn step(&mut self) {
+        // Tick updates
+        while let Some(n) = self.up.pop() {
+            if self.update(n, true) {
+                self.updatable.push(n);
+            }
+        }
+        while let Some(n) = self.down.pop() {
+            if self.update(n, false) {
+                self.updatable.push(n);
+            }
+        }
+
+        // End-of-tick updates
+        for i in 0..self.updatable.len() {
+            let n = self.updatable[i];
+            let Some((prev, next)) = self.late_update(n) else {
+                continue;
+            };
+
+            let edges = self.outgoing.get(n).iter();
+            match (prev, next) {
+                (0, 15) => self.up.extend(edges.map(|&(m, _)| m)),
+                (15, 0) => self.down.extend(edges.map(|&(m, _)| m)),
+                _ if next > prev => self.up.extend(
+                    edges
+                        .filter(|(_, w)| (prev..next).contains(w))
+                        .map(|&(m, _)| m),
+                ),
+                _ => self.down.extend(
+                    edges
+                        .filter(|(_, w)| (next..prev).contains(w))
+                        .map(|&(m, _)| m),
+                ),
+            }
+        }
+        self.updatable.clear();
+
+        self.tick_counter += 1;
+    }
+
+    /// Returns whether any of the edges carries a signal.
+    fn any_input(&self, edges: &Edges, n: u32) -> bool {
+        edges
+            .get(n)
+            .iter()
+            .any(|&(m, w)| self.state.output[m as usize] > w)
+    }
+
+    /// Updates node `n` like `Updatable::update`. Returns whether it requires an end-of-tick update.
+    fn update(&mut self, n: u32, up: bool) -> bool {
+        let i = n as usize;
+        match self.kinds[i] {
+            NodeKind::Trigger | NodeKind::Probe => {
+                self.state.output[i] = if self.any_input(&self.incoming_rear, n) {
+                    15
+                } else {
+                    0
+                };
+                false
+            }
+            NodeKind::SRepeater => {
+                if up {
+                    self.state.on_inputs[i] += 1;
+                    self.state.on_inputs[i] == 1
+                } else {
+                    self.state.on_inputs[i] -= 1;
+                    self.state.on_inputs[i] == 0
+                }
+            }
+            NodeKind::Comparator { mode, entity_power } => {
+                let power = |&(m, w): &(u32, u8)| self.state.output[m as usize].saturating_sub(w);
+                let rear = self
+                    .incoming_rear
+                    .get(n)
+                    .iter()
+                    .map(power)
+                    .fold(entity_power.unwrap_or(0), u8::max);
+                let side = self.incoming_side.get(n).iter().map(power).fold(0, u8::max);
+                self.state.next[i] = match mode {
+                    ComparatorMode::Compare if side <= rear => rear,
+                    ComparatorMode::Compare => 0,
+                    ComparatorMode::Subtract => rear.saturating_sub(side),
+                };
+                self.state.output[i] != self.state.next[i]
+            }
+            NodeKind::Repeater { delay } => {
+                if self.any_input(&self.incoming_side, n) {
+                    return false;
+                }
+
+                let s_new = self.any_input(&self.incoming_rear, n);
+                let locked_next_tick =
+                    self.incoming_side
+                        .get(n)
+                        .iter()
+                        .any(|&(m, _)| match self.kinds[m as usize] {
+                            NodeKind::Repeater { .. } => self.state.locking_signal[m as usize],
+                            NodeKind::Comparator { .. } => self.state.output[m as usize] > 0,
+                            _ => unreachable!(),
+                        });
+
+                if locked_next_tick == self.state.locking_signal[i] {
+                    self.down
+                        .extend(self.outgoing_repeaters.get(n).iter().map(|&(m, _)| m));
+                }
+
+                let powered = self.state.output[i] > 0;
+                let next = self.state.next[i] > 0;
+                if s_new && !next {
+                    // Signal changed upwards: update next signal and reset count.
+                    self.state.next[i] = 1;
+                    self.state.count[i] = 0;
+                } else if !s_new && next && self.state.count[i] == 0 {
+                    // Signal changed downward, and is not propagating already: update next signal.
+                    self.state.next[i] = 0;
+                }
+                let next = self.state.next[i] > 0;
+
+                self.state.locking_signal[i] = if locked_next_tick {
+                    powered
+                } else if self.state.count[i] + 1 == delay {
+                    next
+                } else {
+                    powered
+                };
+
+                powered != next
+            }
+        }
+    }
+
+    /// Updates node `n` like `Updatable::late_update`, returning its previous and new output.
+    fn late_update(&mut self, n: u32) -> Option<(u8, u8)> {
+        let i = n as usize;
+        match self.kinds[i] {
+            NodeKind::Trigger | NodeKind::Probe => unreachable!(),
+            NodeKind::SRepeater => {
+                self.state.output[i] = 15 - self.state.output[i];
+                Some((15 - self.state.output[i], self.state.output[i]))
+            }
+            NodeKind::Comparator { .. } => {
+                let old = self.state.output[i];
+                self.state.output[i] = self.state.next[i];
+                Some((old, self.state.output[i]))
+            }
+            NodeKind::Repeater { delay } => {
+                if self.tick_counter == self.state.last_update[i] {
+                    return None;
+                }
+                self.state.last_update[i] = self.tick_counter;
+
+                self.state.count[i] += 1;
+                self.down.push(n);
+                if self.state.count[i] == delay {
+                    self.state.count[i] = 0;
+                    self.state.output[i] = if self.state.locking_signal[i] { 15 } else { 0 };
+                    if self.state.locking_signal[i] {
+                        Some((0, 15))
+                    } else {
+                        Some((15, 0))
+                    }
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
@@ -23,7 +23,10 @@ pub struct GEdge<N: 'static, E: 'static> {
 
 pub struct FastGraph<N: 'static, E: 'static> {
     phantom: PhantomData<(N, E)>,
+    /// Nodes of the graph, in the order of the `NodeIndex`es they were created from.
     pub nodes: Vec<&'static GNode<N, E>>,
+    /// The `NodeIndex` in the original graph of each node in `nodes`.
+    pub indices: Vec<NodeIndex>,
 }
 
 impl FastGraph<Block, u8> {
@@ -64,8 +67,7 @@ impl FastGraph<Block, u8> {
                             weight: e.weight().strength_loss(),
                             node: map_read[&e.target()],
                         })
-                        .collect_vec()
-                        .into_iter(),
+                        .collect_vec(),
                 );
                 node.incoming_rear = bump.alloc_slice_fill_iter(
                     cblocks
@@ -75,8 +77,7 @@ impl FastGraph<Block, u8> {
                             weight: e.weight().strength_loss(),
                             node: map_read[&e.source()],
                         })
-                        .collect_vec()
-                        .into_iter(),
+                        .collect_vec(),
                 );
                 node.incoming_side = bump.alloc_slice_fill_iter(
                     cblocks
@@ -86,8 +87,7 @@ impl FastGraph<Block, u8> {
                             weight: e.weight().strength_loss(),
                             node: map_read[&e.source()],
                         })
-                        .collect_vec()
-                        .into_iter(),
+                        .collect_vec(),
                 );
             }
         }
@@ -95,13 +95,16 @@ impl FastGraph<Block, u8> {
         // We now just have multiple read-only references
         let nodes: HashMap<NodeIndex, &'static GNode<Block, u8>> = unsafe { mem::transmute(nodes) };
 
-        for (idx, block_ref) in &nodes {
+        let indices = cblocks.node_indices().collect_vec();
+        let nodes = indices.iter().map(|idx| nodes[idx]).collect_vec();
+        for (idx, block_ref) in indices.iter().zip(&nodes) {
             callback(&cblocks[*idx], block_ref);
         }
 
         FastGraph {
             phantom: Default::default(),
-            nodes: nodes.values().cloned().collect_vec(),
+            nodes,
+            indices,
         }
     }
 }
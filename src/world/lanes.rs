@@ -0,0 +1,387 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::world::compiled::{CompileError, CompiledGraph, Csr, NodeKind, NodeState};
+use crate::world::World;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+/// Number of independent simulations run by a `LaneWorld`.
+pub const LANES: usize = 64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LaneError {
+    /// Comparators whose output is not either off or a single signal strength carry analog signals,
+    /// which do not fit in a single bit per lane.
+    Comparator(Option<(isize, isize, isize)>),
+    /// Repeaters that lock each other in a cycle have no order to be updated in.
+    LockingCycle(Option<(isize, isize, isize)>),
+    Compile(CompileError),
+}
+
+impl Display for LaneError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (what, position) = match self {
+            LaneError::Compile(e) => return e.fmt(f),
+            LaneError::Comparator(p) => ("analog comparator", p),
+            LaneError::LockingCycle(p) => ("repeater in a locking cycle", p),
+        };
+        match position {
+            Some((x, y, z)) => write!(f, "{what} at {x},{y},{z} can not be simulated in lanes"),
+            None => write!(f, "{what} can not be simulated in lanes"),
+        }
+    }
+}
+
+impl error::Error for LaneError {}
+
+impl From<CompileError> for LaneError {
+    fn from(e: CompileError) -> Self {
+        LaneError::Compile(e)
+    }
+}
+
+/// Simulates `LANES` copies of a `World` in lockstep, each with its own trigger inputs.
+/// Every value is a `u64` mask that holds the binary signal of one copy per bit.
+///
+/// All nodes are evaluated every tick, so the lanes follow the `World` they were created from
+/// as long as its state is consistent, which holds for schematics saved in-game. Comparators are
+/// supported as long as they either output a single signal strength or nothing.
+pub struct LaneWorld {
+    kinds: Vec<NodeKind>,
+
+    /// Rear edges that carry a signal while their source is on.
+    incoming_rear: Csr,
+
+    /// Side edges that lock a repeater or turn a comparator off while their source is on.
+    incoming_side: Csr,
+
+    /// Side edges of repeaters, through which they are locked at the end of a tick.
+    locking: Csr,
+
+    /// Order in which the nodes are updated, where repeaters come after the repeaters that lock
+    /// them.
+    order: Vec<u32>,
+
+    triggers: Vec<(String, u32)>,
+    probes: Vec<(String, u32)>,
+
+    /// Output of every node. For probes, this is the signal they currently detect.
+    outputs: Vec<u64>,
+    next_outputs: Vec<u64>,
+
+    /// Whether any input of an `SRepeater` was powered during the previous tick.
+    inputs_on: Vec<u64>,
+
+    /// Next output of a repeater when its count reaches the repeater delay.
+    next_powered: Vec<u64>,
+
+    /// Low and high bit of the count of a repeater.
+    count: Vec<[u64; 2]>,
+
+    /// Global tick counter.
+    tick_counter: usize,
+}
+
+/// Returns a lane mask with every lane set to `v`.
+fn splat(v: bool) -> u64 {
+    if v {
+        u64::MAX
+    } else {
+        0
+    }
+}
+
+/// Returns the edges of `csr` for which `keep` returns true, given the node, the other end and
+/// the strength loss of the edge.
+fn filter(csr: &Csr, keep: impl Fn(u32, u32, u8) -> bool) -> Csr {
+    let mut result = Csr {
+        offsets: vec![0],
+        ..Default::default()
+    };
+    for n in 0..csr.offsets.len() as u32 - 1 {
+        for (m, w) in csr.edges(n).filter(|&(m, w)| keep(n, m, w)) {
+            result.nodes.push(m);
+            result.weights.push(w);
+        }
+        result.offsets.push(result.nodes.len() as u32);
+    }
+    result
+}
+
+/// Returns the signal strength every node outputs while it is on. Fails at a comparator that does
+/// not output a single signal strength, like `prune_binary_comparators` decides.
+fn strengths(graph: &CompiledGraph) -> Result<Vec<u8>, LaneError> {
+    let mut strengths: Vec<Option<u8>> = graph
+        .kinds
+        .iter()
+        .map(|kind| match kind {
+            NodeKind::Comparator { .. } => None,
+            _ => Some(15),
+        })
+        .collect();
+
+    // Comparators that output to comparators are resolved after them.
+    loop {
+        let mut progress = false;
+        for (i, kind) in graph.kinds.iter().enumerate() {
+            let NodeKind::Comparator { mode, entity_power } = *kind else {
+                continue;
+            };
+            if strengths[i].is_some() {
+                continue;
+            }
+            let error = LaneError::Comparator(graph.positions[i]);
+            if entity_power.is_some() {
+                return Err(error);
+            }
+
+            let n = i as u32;
+            let strength = |(m, w): (u32, u8)| Some(strengths[m as usize]?.saturating_sub(w));
+            let Some(rear) = graph
+                .incoming_rear
+                .edges(n)
+                .map(strength)
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let Some(side) = graph
+                .incoming_side
+                .edges(n)
+                .map(strength)
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+
+            let rear = rear.into_iter().filter(|&s| s > 0).collect::<Vec<_>>();
+            let on = rear.first().copied().unwrap_or(0);
+            if rear.iter().any(|&s| s != on)
+                || mode == ComparatorMode::Subtract && side.iter().any(|&s| s > 0 && s < on)
+                || ![0, on].contains(&NodeState::from(&graph.states[i]).output)
+            {
+                return Err(error);
+            }
+            strengths[i] = Some(on);
+            progress = true;
+        }
+        if !progress {
+            break;
+        }
+    }
+
+    // Comparators that are left output to each other in a cycle.
+    match strengths.iter().position(Option::is_none) {
+        Some(i) => Err(LaneError::Comparator(graph.positions[i])),
+        None => Ok(strengths.into_iter().map(Option::unwrap).collect()),
+    }
+}
+
+/// Returns the nodes in an order where every repeater comes after the repeaters that lock it.
+fn locking_order(graph: &CompiledGraph) -> Result<Vec<u32>, LaneError> {
+    let is_repeater = |n: u32| matches!(graph.kinds[n as usize], NodeKind::Repeater { .. });
+
+    // Kahn's algorithm, where every repeater waits for the repeaters that lock it.
+    let mut locks = vec![Vec::new(); graph.len()];
+    let mut waiting = vec![0; graph.len()];
+    for n in (0..graph.len() as u32).filter(|&n| is_repeater(n)) {
+        for (m, _) in graph
+            .incoming_side
+            .edges(n)
+            .filter(|&(m, _)| is_repeater(m))
+        {
+            locks[m as usize].push(n);
+            waiting[n as usize] += 1;
+        }
+    }
+    let mut order: Vec<u32> = (0..graph.len() as u32)
+        .filter(|&n| waiting[n as usize] == 0)
+        .collect();
+    let mut i = 0;
+    while let Some(&n) = order.get(i) {
+        for &m in &locks[n as usize] {
+            waiting[m as usize] -= 1;
+            if waiting[m as usize] == 0 {
+                order.push(m);
+            }
+        }
+        i += 1;
+    }
+
+    match waiting.iter().position(|&w| w > 0) {
+        Some(i) => Err(LaneError::LockingCycle(graph.positions[i])),
+        None => Ok(order),
+    }
+}
+
+impl LaneWorld {
+    /// Creates a `LaneWorld` with every lane in the current state of `world`.
+    /// Fails if the world contains blocks whose state does not fit in one bit.
+    pub fn new(world: &World) -> Result<Self, LaneError> {
+        let graph = CompiledGraph::new(world)?;
+        let strengths = strengths(&graph)?;
+        let order = locking_order(&graph)?;
+
+        let n = graph.len();
+        let mut outputs = vec![0; n];
+        let mut inputs_on = vec![0; n];
+        let mut next_powered = vec![0; n];
+        let mut count = vec![[0; 2]; n];
+        for (i, state) in graph.node_states().enumerate() {
+            outputs[i] = splat(state.output > 0);
+            inputs_on[i] = splat(state.on_inputs > 0);
+            next_powered[i] = splat(state.next > 0);
+            count[i] = [splat(state.count & 1 != 0), splat(state.count & 2 != 0)];
+        }
+
+        let kinds = &graph.kinds;
+        let incoming_side = filter(&graph.incoming_side, |n, m, w| {
+            let strength = strengths[m as usize].saturating_sub(w);
+            match kinds[n as usize] {
+                // Like `BinaryComparator`, side inputs either turn the output off or do nothing.
+                NodeKind::Comparator {
+                    mode: ComparatorMode::Compare,
+                    ..
+                } => strength > strengths[n as usize],
+                NodeKind::Comparator {
+                    mode: ComparatorMode::Subtract,
+                    ..
+                } => strength > 0 && strength >= strengths[n as usize],
+                _ => strength > 0,
+            }
+        });
+        let locking = filter(&graph.incoming_side, |n, _, _| {
+            matches!(kinds[n as usize], NodeKind::Repeater { .. })
+        });
+
+        Ok(LaneWorld {
+            incoming_rear: filter(&graph.incoming_rear, |_, m, w| strengths[m as usize] > w),
+            incoming_side,
+            locking,
+            order,
+            kinds: graph.kinds,
+            triggers: graph.triggers,
+            probes: graph.probes,
+            next_outputs: outputs.clone(),
+            outputs,
+            inputs_on,
+            next_powered,
+            count,
+            tick_counter: world.tick_counter(),
+        })
+    }
+
+    /// Powers every trigger with the given name in the lanes set in `lanes`, and unpowers it
+    /// in the others. Returns `false` if there is no such trigger.
+    pub fn set_trigger(&mut self, name: &str, lanes: u64) -> bool {
+        let mut found = false;
+        for (_, i) in self.triggers.iter().filter(|(s, _)| s == name) {
+            self.outputs[*i as usize] = lanes;
+            found = true;
+        }
+        found
+    }
+
+    /// Returns the lanes in which the probe is currently powered.
+    pub fn get_probe(&self, name: &str) -> Option<u64> {
+        self.probes
+            .iter()
+            .find(|(s, _)| s == name)
+            .map(|(_, i)| self.outputs[*i as usize])
+    }
+
+    /// Returns `HashMap` from the names of probes to the lanes in which they are powered.
+    pub fn get_probes(&self) -> HashMap<&str, u64> {
+        self.probes
+            .iter()
+            .map(|(s, i)| (s.as_str(), self.outputs[*i as usize]))
+            .collect()
+    }
+
+    /// Returns the number of ticks simulated so far.
+    pub fn tick_counter(&self) -> usize {
+        self.tick_counter
+    }
+
+    pub fn step(&mut self) {
+        for k in 0..self.order.len() {
+            let n = self.order[k];
+            let i = n as usize;
+            let on = self.any_input(&self.incoming_rear, n);
+            let out = self.outputs[i];
+
+            self.next_outputs[i] = match self.kinds[i] {
+                NodeKind::Trigger => out,
+                NodeKind::Probe => on,
+                NodeKind::SRepeater => {
+                    let toggle = on ^ self.inputs_on[i];
+                    self.inputs_on[i] = on;
+                    out ^ toggle
+                }
+                NodeKind::Repeater { delay } => {
+                    let locked = self.any_input(&self.incoming_side, n);
+                    // Repeaters that lock this one already have their output of the next tick.
+                    let locked_next_tick = self.locking.edges(n).fold(0, |acc, (m, _)| {
+                        acc | match self.kinds[m as usize] {
+                            NodeKind::Repeater { .. } => self.next_outputs[m as usize],
+                            _ => self.outputs[m as usize],
+                        }
+                    });
+                    self.step_repeater(i, delay, on, locked, locked_next_tick)
+                }
+                NodeKind::Comparator { .. } => on & !self.any_input(&self.incoming_side, n),
+            };
+        }
+
+        std::mem::swap(&mut self.outputs, &mut self.next_outputs);
+        self.tick_counter += 1;
+    }
+
+    /// Returns the lanes in which any of the edges carries a signal.
+    fn any_input(&self, edges: &Csr, n: u32) -> u64 {
+        edges
+            .edges(n)
+            .fold(0, |acc, (m, _)| acc | self.outputs[m as usize])
+    }
+
+    /// Updates the repeater at `i` whose rear is powered in the lanes in `on`, and returns its
+    /// next output. Nothing changes in the lanes in `locked`, and the output is kept in the lanes
+    /// in `locked_next_tick`.
+    fn step_repeater(
+        &mut self,
+        i: usize,
+        delay: u8,
+        on: u64,
+        locked: u64,
+        locked_next_tick: u64,
+    ) -> u64 {
+        let mut powered = self.outputs[i];
+        let mut next = self.next_powered[i];
+        let [mut c0, mut c1] = self.count[i];
+
+        // Signal changed upwards: update next signal and reset count.
+        let rising = on & !next & !locked;
+        next |= rising;
+        c0 &= !rising;
+        c1 &= !rising;
+
+        // Signal changed downward, and is not propagating already: update next signal.
+        let falling = !on & next & !(c0 | c1) & !locked;
+        next &= !falling;
+
+        // Count while the output differs from the next output, and switch at the delay.
+        let counting = (powered ^ next) & !locked;
+        let last = delay - 1;
+        let at_last =
+            (if last & 1 != 0 { c0 } else { !c0 }) & (if last & 2 != 0 { c1 } else { !c1 });
+        let fire = counting & at_last;
+        // A repeater that is locked by the end of the tick keeps its output, but its count restarts.
+        powered ^= fire & !locked_next_tick;
+        c1 = (c1 ^ (c0 & counting)) & !fire;
+        c0 = (c0 ^ counting) & !fire;
+
+        self.next_powered[i] = next;
+        self.count[i] = [c0, c1];
+        powered
+    }
+}
@@ -0,0 +1,401 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::CBlock;
+use crate::world::provenance::{Position, Provenance};
+use crate::world::{CBlockGraph, World};
+use itertools::Itertools;
+use petgraph::algo::tarjan_scc;
+use petgraph::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+/// Most inputs a probe can have for `ProbeLogic::truth_table` to enumerate them.
+pub const MAX_TABLE_INPUTS: usize = 16;
+
+/// Boolean expression over the triggers of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum Expr {
+    Const(bool),
+
+    /// Whether the trigger with the given name is powered.
+    Input(String),
+
+    /// Whether a node with feedback, or a repeater that can be locked, outputs at least the given
+    /// signal strength. These nodes remember their output, so it is not a function of the inputs.
+    State(Position, u8),
+
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    fn not(e: Expr) -> Expr {
+        match e {
+            Expr::Const(b) => Expr::Const(!b),
+            Expr::Not(e) => *e,
+            e => Expr::Not(Box::new(e)),
+        }
+    }
+
+    /// Conjunction of the expressions, simplified where it is trivial.
+    fn and(es: impl IntoIterator<Item = Expr>) -> Expr {
+        let mut terms = vec![];
+        for e in es {
+            match e {
+                Expr::Const(true) => {}
+                Expr::Const(false) => return Expr::Const(false),
+                Expr::And(es) => terms.extend(es),
+                e => terms.push(e),
+            }
+        }
+        let mut terms = terms.into_iter().unique().collect_vec();
+        if has_complement(&terms) {
+            return Expr::Const(false);
+        }
+        match terms.len() {
+            0 => Expr::Const(true),
+            1 => terms.pop().unwrap(),
+            _ => Expr::And(terms),
+        }
+    }
+
+    /// Disjunction of the expressions, simplified where it is trivial.
+    fn or(es: impl IntoIterator<Item = Expr>) -> Expr {
+        let mut terms = vec![];
+        for e in es {
+            match e {
+                Expr::Const(false) => {}
+                Expr::Const(true) => return Expr::Const(true),
+                Expr::Or(es) => terms.extend(es),
+                e => terms.push(e),
+            }
+        }
+        let mut terms = terms.into_iter().unique().collect_vec();
+        if has_complement(&terms) {
+            return Expr::Const(true);
+        }
+        match terms.len() {
+            0 => Expr::Const(false),
+            1 => terms.pop().unwrap(),
+            _ => Expr::Or(terms),
+        }
+    }
+
+    /// Returns the names of the triggers the expression depends on.
+    pub fn inputs(&self) -> BTreeSet<&str> {
+        let mut inputs = BTreeSet::new();
+        let mut todo = vec![self];
+        while let Some(e) = todo.pop() {
+            match e {
+                Expr::Input(name) => {
+                    inputs.insert(name.as_str());
+                }
+                Expr::Not(e) => todo.push(e),
+                Expr::And(es) | Expr::Or(es) => todo.extend(es),
+                Expr::Const(_) | Expr::State(..) => {}
+            }
+        }
+        inputs
+    }
+
+    /// Whether the expression depends on the state of a node.
+    pub fn is_sequential(&self) -> bool {
+        match self {
+            Expr::State(..) => true,
+            Expr::Not(e) => e.is_sequential(),
+            Expr::And(es) | Expr::Or(es) => es.iter().any(Expr::is_sequential),
+            Expr::Const(_) | Expr::Input(_) => false,
+        }
+    }
+
+    /// Evaluates the expression, with the triggers that are powered. Panics on `Expr::State`.
+    pub fn eval(&self, powered: &HashSet<&str>) -> bool {
+        match self {
+            Expr::Const(b) => *b,
+            Expr::Input(name) => powered.contains(name.as_str()),
+            Expr::State(..) => panic!("Cannot evaluate the state of a node."),
+            Expr::Not(e) => !e.eval(powered),
+            Expr::And(es) => es.iter().all(|e| e.eval(powered)),
+            Expr::Or(es) => es.iter().any(|e| e.eval(powered)),
+        }
+    }
+}
+
+/// Whether the terms contain both an expression and its negation.
+fn has_complement(terms: &[Expr]) -> bool {
+    terms.iter().any(|e| match e {
+        Expr::Not(inner) => terms.contains(inner),
+        _ => false,
+    })
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Operands that bind weaker than the operator are put between parentheses.
+        let operand = |e: &Expr, f: &mut fmt::Formatter<'_>, strength: u8| {
+            let binds = match e {
+                Expr::Or(_) => 0,
+                Expr::And(_) => 1,
+                _ => 2,
+            };
+            if binds < strength {
+                write!(f, "({e})")
+            } else {
+                write!(f, "{e}")
+            }
+        };
+        match self {
+            Expr::Const(b) => write!(f, "{}", *b as u8),
+            // Names that could be mistaken for constants or operators are quoted.
+            Expr::Input(name)
+                if name.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                write!(f, "{name}")
+            }
+            Expr::Input(name) => write!(f, "{name:?}"),
+            Expr::State(p, 1) => write!(f, "state{p:?}"),
+            Expr::State(p, s) => write!(f, "state{p:?}>={s}"),
+            Expr::Not(e) => {
+                write!(f, "!")?;
+                operand(e, f, 2)
+            }
+            Expr::And(es) => {
+                for (i, e) in es.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " & ")?;
+                    }
+                    operand(e, f, 1)?;
+                }
+                Ok(())
+            }
+            Expr::Or(es) => {
+                for (i, e) in es.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    operand(e, f, 0)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// What a probe computes once the circuit is stable.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProbeLogic {
+    pub probe: String,
+
+    /// Whether the probe is powered.
+    pub expr: Expr,
+}
+
+impl ProbeLogic {
+    /// Whether the probe depends on nodes with feedback or repeaters that can be locked, in which
+    /// case it does not compute a function of the triggers.
+    pub fn is_sequential(&self) -> bool {
+        self.expr.is_sequential()
+    }
+
+    /// Returns the value of the probe for every combination of the triggers it depends on, or
+    /// `None` if the probe is sequential or depends on more than `MAX_TABLE_INPUTS` triggers.
+    pub fn truth_table(&self) -> Option<TruthTable> {
+        let inputs = self.expr.inputs();
+        if self.is_sequential() || inputs.len() > MAX_TABLE_INPUTS {
+            return None;
+        }
+
+        let rows = (0..1usize << inputs.len())
+            .map(|row| {
+                let values = (0..inputs.len())
+                    .map(|i| row & (1 << (inputs.len() - 1 - i)) != 0)
+                    .collect_vec();
+                let powered = inputs
+                    .iter()
+                    .zip(&values)
+                    .filter(|(_, &v)| v)
+                    .map(|(&name, _)| name)
+                    .collect();
+                let output = self.expr.eval(&powered);
+                (values, output)
+            })
+            .collect();
+        Some(TruthTable {
+            inputs: inputs.into_iter().map(String::from).collect(),
+            output: self.probe.clone(),
+            rows,
+        })
+    }
+}
+
+/// Value of a probe for every combination of its inputs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TruthTable {
+    pub inputs: Vec<String>,
+    pub output: String,
+    pub rows: Vec<(Vec<bool>, bool)>,
+}
+
+impl fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} | {}", self.inputs.join(" "), self.output)?;
+        for (values, output) in &self.rows {
+            writeln!(f)?;
+            for (name, &v) in self.inputs.iter().zip(values) {
+                write!(f, "{:<w$} ", v as u8, w = name.len())?;
+            }
+            write!(f, "| {}", *output as u8)?;
+        }
+        Ok(())
+    }
+}
+
+/// Walks the pruned graph back from the probes to the triggers, like `prune_irrelevant`.
+struct Extractor<'a> {
+    cblocks: &'a CBlockGraph,
+    provenance: &'a Provenance,
+
+    /// Nodes with feedback.
+    cyclic: HashSet<NodeIndex>,
+
+    /// Expressions for whether a node outputs at least a signal strength.
+    memo: HashMap<(NodeIndex, u8), Expr>,
+}
+
+impl Extractor<'_> {
+    /// Returns whether the node outputs a signal strength of at least `strength`.
+    fn at_least(&mut self, idx: NodeIndex, strength: u8) -> Expr {
+        if strength > 15 {
+            return Expr::Const(false);
+        }
+        if let Some(e) = self.memo.get(&(idx, strength)) {
+            return e.clone();
+        }
+
+        let cblocks = self.cblocks;
+        let lockable = matches!(cblocks[idx], CBlock::Repeater(_))
+            && cblocks
+                .edges_directed(idx, Incoming)
+                .any(|e| e.weight().is_side());
+        let e = if self.cyclic.contains(&idx) || lockable {
+            let position = self.provenance.position(idx).unwrap_or_default();
+            match cblocks[idx] {
+                CBlock::Comparator(_) | CBlock::BinaryComparator(_) => {
+                    Expr::State(position, strength)
+                }
+                _ => Expr::State(position, 1),
+            }
+        } else {
+            match &cblocks[idx] {
+                CBlock::Trigger(t) => Expr::Input(t.name.clone()),
+                CBlock::RedstoneBlock(_) => Expr::Const(true),
+                CBlock::Torch(_) => Expr::not(self.input(idx, false, 1)),
+                CBlock::Repeater(_) | CBlock::SRepeater(_) | CBlock::DelayLine(_) => {
+                    self.input(idx, false, 1)
+                }
+                CBlock::Buffer(b) if b.is_inverted() => Expr::not(self.input(idx, false, 1)),
+                CBlock::Buffer(_) => self.input(idx, false, 1),
+                CBlock::Comparator(c) => {
+                    self.comparator(idx, c.mode(), c.entity_power().unwrap_or(0), strength)
+                }
+                CBlock::BinaryComparator(c) => self.comparator(idx, c.mode(), 0, strength),
+                CBlock::Probe(_)
+                | CBlock::Redstone(_)
+                | CBlock::SolidWeak(_)
+                | CBlock::SolidStrong(_) => unreachable!("Block does not output a signal."),
+            }
+        };
+
+        self.memo.insert((idx, strength), e.clone());
+        e
+    }
+
+    /// Returns whether any rear or side input of the node has at least `strength`.
+    fn input(&mut self, idx: NodeIndex, side: bool, strength: u8) -> Expr {
+        let inputs = self
+            .cblocks
+            .edges_directed(idx, Incoming)
+            .filter(|e| e.weight().is_side() == side)
+            .map(|e| (e.source(), e.weight().strength_loss()))
+            .collect_vec();
+        Expr::or(
+            inputs
+                .into_iter()
+                .map(|(source, loss)| self.at_least(source, strength.saturating_add(loss))),
+        )
+    }
+
+    /// Returns whether the comparator outputs at least `strength`.
+    fn comparator(
+        &mut self,
+        idx: NodeIndex,
+        mode: ComparatorMode,
+        entity: u8,
+        strength: u8,
+    ) -> Expr {
+        let rear = |s: &mut Self, v: u8| {
+            if entity >= v {
+                Expr::Const(true)
+            } else {
+                s.input(idx, false, v)
+            }
+        };
+        match mode {
+            // The rear input is at least `strength`, and no weaker than the side input.
+            ComparatorMode::Compare => {
+                let mut terms = vec![rear(self, strength)];
+                for v in strength + 1..=15 {
+                    let side = self.input(idx, true, v);
+                    terms.push(Expr::or([Expr::not(side), rear(self, v)]));
+                }
+                Expr::and(terms)
+            }
+            // The rear input is at least `v` and the side input at most `v - strength`.
+            ComparatorMode::Subtract => Expr::or(
+                (strength..=15)
+                    .map(|v| {
+                        let side = self.input(idx, true, v - strength + 1);
+                        Expr::and([rear(self, v), Expr::not(side)])
+                    })
+                    .collect_vec(),
+            ),
+        }
+    }
+}
+
+/// Extracts what every probe computes in terms of the triggers, sorted by probe.
+pub fn extract(cblocks: &CBlockGraph, provenance: &Provenance) -> Vec<ProbeLogic> {
+    let cyclic = tarjan_scc(cblocks)
+        .into_iter()
+        .filter(|c| c.len() > 1 || cblocks.contains_edge(c[0], c[0]))
+        .flatten()
+        .collect();
+    let mut extractor = Extractor {
+        cblocks,
+        provenance,
+        cyclic,
+        memo: HashMap::new(),
+    };
+
+    cblocks
+        .node_indices()
+        .filter_map(|idx| match &cblocks[idx] {
+            CBlock::Probe(p) => Some((p.name.clone(), idx)),
+            _ => None,
+        })
+        .sorted()
+        .map(|(probe, idx)| ProbeLogic {
+            probe,
+            expr: extractor.input(idx, false, 1),
+        })
+        .collect()
+}
+
+impl World {
+    /// Extracts what every probe computes in terms of the triggers, sorted by probe.
+    pub fn probe_logic(&self) -> Vec<ProbeLogic> {
+        extract(&self.cblocks, &self.provenance)
+    }
+}
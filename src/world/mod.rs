@@ -1,15 +1,32 @@
+pub mod builder;
+pub mod codegen;
+pub mod compiled;
 pub mod create;
 pub mod data;
 pub mod edge;
+pub mod flat;
 pub mod graph;
-mod prune;
+pub mod lanes;
+pub mod logic;
+pub mod netlist;
+pub mod oscillation;
+pub mod parallel;
+pub mod provenance;
+pub mod prune;
+pub mod reference;
 pub mod schematic;
+pub mod state;
 mod step;
+pub mod text;
+pub mod timing;
+pub mod verilog;
 
 use crate::blocks::Block;
-use crate::blocks::{CBlock, OutputPower};
+use crate::blocks::{BlockState, CBlock, OutputPower, RuntimeState};
 use crate::world::edge::Edge;
 use crate::world::graph::{FastGraph, GNode};
+use crate::world::provenance::Provenance;
+use crate::world::prune::PruneStats;
 use petgraph::prelude::{NodeIndex, StableGraph};
 use std::collections::HashMap;
 
@@ -26,12 +43,15 @@ pub struct World {
     pub cblocks: CBlockGraph,
     pub cblock_positions: HashMap<NodeIndex, (isize, isize, isize)>,
 
+    /// Blocks of the schematic that the nodes and edges of `cblocks` stand for.
+    pub provenance: Provenance,
+
     /// Holds the graph of the redstone circuit.
     // todo: make this private, requires implementation of Display for `World`.
     pub blocks: BlockGraph,
 
-    /// Stores the indexes of the triggers in the `blocks` graph.
-    triggers: Vec<&'static GNode<Block, u8>>,
+    /// Stores the indexes of the triggers in the `blocks` graph, together with their names.
+    triggers: Vec<(String, &'static GNode<Block, u8>)>,
 
     /// Stores a bijective map of the indexes the probes in the `blocks` graph to their names.
     probes: HashMap<String, &'static GNode<Block, u8>>,
@@ -44,9 +64,30 @@ pub struct World {
 
     /// Global tick counter.
     tick_counter: usize,
+
+    /// Probes with the value they had when their changes were last reported, sorted by name.
+    probe_values: Vec<(String, &'static GNode<Block, u8>, bool)>,
+
+    /// Whether any probe was updated since their changes were last reported.
+    probes_updated: bool,
+
+    /// Statistics of the pruning passes that ran when the world was constructed.
+    prune_stats: PruneStats,
+}
+
+/// Error returned when a world does not become stable within the given number of ticks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Oscillating {
+    /// Number of ticks that were simulated.
+    pub ticks: usize,
 }
 
 impl World {
+    /// Returns statistics of the pruning passes that ran when the world was constructed.
+    pub fn prune_stats(&self) -> &PruneStats {
+        &self.prune_stats
+    }
+
     /// Returns whether the probe is currently powered.
     pub fn get_probe(&self, name: &str) -> Option<bool> {
         let Block::Redstone(v) = &self.probes.get(name)?.weight else {
@@ -55,6 +96,44 @@ impl World {
         Some(v.output_power() > 0)
     }
 
+    /// Returns the names of the triggers, which may contain duplicates.
+    pub fn trigger_names(&self) -> impl Iterator<Item = &str> {
+        self.triggers.iter().map(|(s, _)| s.as_str())
+    }
+
+    /// Returns the runtime state of every node, in the order of `blocks`.
+    pub fn states(&self) -> Vec<BlockState> {
+        self.blocks.nodes.iter().map(|n| n.weight.state()).collect()
+    }
+
+    /// Returns the number of ticks simulated so far.
+    pub fn tick_counter(&self) -> usize {
+        self.tick_counter
+    }
+
+    /// Returns the probes whose value changed since the last time changes were reported,
+    /// together with their new value.
+    pub fn probe_changes(&mut self) -> Vec<(&str, bool)> {
+        if !self.probes_updated {
+            return Vec::new();
+        }
+        self.probes_updated = false;
+
+        self.probe_values
+            .iter_mut()
+            .filter_map(|(s, i, v)| {
+                let Block::Redstone(r) = &i.weight else {
+                    panic!("Probe was not a `Redstone` block, something went wrong!");
+                };
+                let new = r.output_power() > 0;
+                (new != *v).then(|| {
+                    *v = new;
+                    (s.as_str(), new)
+                })
+            })
+            .collect()
+    }
+
     /// Returns `HashMap` from the names of probes to whether they are currently powered.
     pub fn get_probes(&self) -> HashMap<&str, bool> {
         self.probes
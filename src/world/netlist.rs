@@ -0,0 +1,475 @@
+use crate::blocks::probe::CProbe;
+use crate::blocks::redstone_block::CRedstoneBlock;
+use crate::blocks::repeater::CRepeater;
+use crate::blocks::torch::CTorch;
+use crate::blocks::trigger::CTrigger;
+use crate::blocks::CBlock;
+use crate::world::edge::Edge;
+use crate::world::provenance::Provenance;
+use crate::world::prune::PruneOptions;
+use crate::world::{CBlockGraph, World};
+use itertools::Itertools;
+use petgraph::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::{error, fmt};
+
+/// Gate-level netlist, which `World::from_netlist` builds out of redstone blocks with the timing
+/// they have in Minecraft. Read from a JSON gate list, or from the combinational and latch subset
+/// of BLIF.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Netlist {
+    /// Signals driven by triggers.
+    pub inputs: Vec<String>,
+
+    /// Signals read by probes.
+    pub outputs: Vec<String>,
+
+    pub gates: Vec<Gate>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Gate {
+    #[serde(flatten)]
+    pub kind: GateKind,
+    pub inputs: Vec<String>,
+    pub output: String,
+}
+
+/// Kind of a gate, and the blocks it is built from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GateKind {
+    /// Repeater with a delay of 1 to 4 ticks.
+    Buf {
+        #[serde(default = "default_delay")]
+        delay: u8,
+    },
+
+    /// Torch, 1 tick.
+    Not,
+
+    /// Torch powered by torches on every input, 2 ticks.
+    And,
+
+    /// Inputs joined by a wire, 0 ticks.
+    Or,
+
+    /// Torches on every input joined by a wire, 1 tick.
+    Nand,
+
+    /// Torch powered by every input, 1 tick.
+    Nor,
+
+    /// Two torches that each see one input and the inverse of the other, 2 ticks.
+    Xor,
+
+    /// Torch powered by a `Xor`, 3 ticks.
+    Xnor,
+
+    /// Sum of products as in BLIF, which outputs `value` if the inputs match any of the cubes of
+    /// `0`, `1` and `-`, and `!value` otherwise. Every cube is a torch powered by the inputs that
+    /// must be off and by torches on the inputs that must be on, 2 ticks, or 3 ticks if `value`
+    /// is false.
+    Cover { cubes: Vec<String>, value: bool },
+
+    /// Repeater with a delay of 1 tick, where `init` picks the initial state of loops that can
+    /// settle in more than one state.
+    Latch {
+        #[serde(default)]
+        init: bool,
+    },
+}
+
+fn default_delay() -> u8 {
+    1
+}
+
+#[derive(Debug)]
+pub enum NetlistError {
+    Json(serde_json::Error),
+    /// Malformed or unsupported BLIF, with its line number.
+    Blif(usize, String),
+    /// A gate reads a signal that is neither an input nor the output of a gate.
+    Undefined(String),
+    /// A signal is driven by more than one gate or input.
+    MultipleDrivers(String),
+    /// A gate has the wrong number of inputs or invalid parameters.
+    InvalidGate(String),
+    /// A signal depends on itself through wires alone.
+    WireLoop(String),
+    /// A signal is in a loop of gates that never settles while every input is off, such as a
+    /// ring oscillator.
+    Unsettled(String),
+}
+
+impl Display for NetlistError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NetlistError::Json(e) => write!(f, "malformed netlist: {e}"),
+            NetlistError::Blif(line, message) => write!(f, "line {line}: {message}"),
+            NetlistError::Undefined(s) => write!(f, "signal {s} is never driven"),
+            NetlistError::MultipleDrivers(s) => write!(f, "signal {s} is driven more than once"),
+            NetlistError::InvalidGate(s) => write!(f, "invalid gate driving {s}"),
+            NetlistError::WireLoop(s) => write!(f, "signal {s} is in a loop of wires"),
+            NetlistError::Unsettled(s) => write!(f, "signal {s} never settles"),
+        }
+    }
+}
+
+impl error::Error for NetlistError {}
+
+impl From<serde_json::Error> for NetlistError {
+    fn from(e: serde_json::Error) -> Self {
+        NetlistError::Json(e)
+    }
+}
+
+impl Netlist {
+    pub fn from_json(s: &str) -> Result<Netlist, NetlistError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Reads the first model of a BLIF file, which may only contain `.inputs`, `.outputs`,
+    /// `.names` and `.latch`.
+    pub fn from_blif(s: &str) -> Result<Netlist, NetlistError> {
+        let mut netlist = Netlist::default();
+
+        // Joins continued lines and strips comments, keeping the number of the first line.
+        let mut lines = vec![];
+        let mut continued = false;
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim_end();
+            let (line, continues) = match line.strip_suffix('\\') {
+                Some(line) => (line, true),
+                None => (line, false),
+            };
+            if continued {
+                let (_, last): &mut (usize, String) = lines.last_mut().unwrap();
+                last.push(' ');
+                last.push_str(line);
+            } else if !line.trim().is_empty() {
+                lines.push((i + 1, line.to_string()));
+            }
+            continued = continues;
+        }
+
+        let mut lines = lines.into_iter().peekable();
+        while let Some((n, line)) = lines.next() {
+            let mut words = line.split_whitespace();
+            let command = words.next().unwrap();
+            let words = words.map(String::from).collect_vec();
+            match command {
+                ".model" => {}
+                ".end" => break,
+                ".inputs" => netlist.inputs.extend(words),
+                ".outputs" => netlist.outputs.extend(words),
+                ".names" => {
+                    let Some((output, inputs)) = words.split_last() else {
+                        return Err(NetlistError::Blif(n, ".names without signals".to_string()));
+                    };
+                    let mut cubes = vec![];
+                    let mut value = None;
+                    while let Some((n, row)) = lines.next_if(|(_, l)| !l.starts_with('.')) {
+                        let (cube, v) = match row.split_whitespace().collect_vec()[..] {
+                            [v] if inputs.is_empty() => ("", v),
+                            [cube, v] => (cube, v),
+                            _ => return Err(NetlistError::Blif(n, "malformed cube".to_string())),
+                        };
+                        let v = match v {
+                            "0" => false,
+                            "1" => true,
+                            _ => return Err(NetlistError::Blif(n, "malformed cube".to_string())),
+                        };
+                        if value.is_some_and(|value| value != v) {
+                            return Err(NetlistError::Blif(n, "mixed on- and off-set".to_string()));
+                        }
+                        value = Some(v);
+                        cubes.push(cube.to_string());
+                    }
+                    netlist.gates.push(Gate {
+                        kind: GateKind::Cover {
+                            cubes,
+                            value: value.unwrap_or(true),
+                        },
+                        inputs: inputs.to_vec(),
+                        output: output.clone(),
+                    });
+                }
+                ".latch" => {
+                    let [input, output, rest @ ..] = &words[..] else {
+                        return Err(NetlistError::Blif(n, ".latch without signals".to_string()));
+                    };
+                    // The optional type and control are ignored, every latch is clocked by ticks.
+                    let init = matches!(rest.last().map(String::as_str), Some("1"));
+                    netlist.gates.push(Gate {
+                        kind: GateKind::Latch { init },
+                        inputs: vec![input.clone()],
+                        output: output.clone(),
+                    });
+                }
+                _ => return Err(NetlistError::Blif(n, format!("unsupported {command}"))),
+            }
+        }
+
+        Ok(netlist)
+    }
+}
+
+/// Output of a block, or a signal whose drivers are resolved once every gate has its blocks.
+#[derive(Copy, Clone)]
+enum Source<'a> {
+    Node(NodeIndex),
+    Signal(&'a str),
+}
+
+struct Builder<'a> {
+    cblocks: CBlockGraph,
+
+    /// Sources that are joined by a wire to form every signal.
+    signals: HashMap<&'a str, Vec<Source<'a>>>,
+
+    /// Sources that power every block.
+    inputs: Vec<(Source<'a>, NodeIndex)>,
+
+    /// Torch on every signal, shared by the gates that need its inverse.
+    inverted: HashMap<&'a str, NodeIndex>,
+}
+
+impl<'a> Builder<'a> {
+    fn block(&mut self, cblock: CBlock, inputs: impl IntoIterator<Item = Source<'a>>) -> NodeIndex {
+        let idx = self.cblocks.add_node(cblock);
+        self.inputs.extend(inputs.into_iter().map(|s| (s, idx)));
+        idx
+    }
+
+    fn torch(&mut self, inputs: impl IntoIterator<Item = Source<'a>>) -> Source<'a> {
+        Source::Node(self.block(CBlock::Torch(CTorch::with_lit(true)), inputs))
+    }
+
+    fn not(&mut self, signal: &'a str) -> Source<'a> {
+        if let Some(&idx) = self.inverted.get(signal) {
+            return Source::Node(idx);
+        }
+        let Source::Node(idx) = self.torch([Source::Signal(signal)]) else {
+            unreachable!();
+        };
+        self.inverted.insert(signal, idx);
+        Source::Node(idx)
+    }
+
+    /// Adds the blocks of the gate, returning the sources that form its output.
+    fn gate(&mut self, gate: &'a Gate) -> Result<Vec<Source<'a>>, NetlistError> {
+        let invalid = || NetlistError::InvalidGate(gate.output.clone());
+        let inputs = gate.inputs.iter().map(|s| Source::Signal(s)).collect_vec();
+        let arity_ok = match gate.kind {
+            GateKind::Not | GateKind::Latch { .. } => inputs.len() == 1,
+            GateKind::Xor | GateKind::Xnor => inputs.len() == 2,
+            GateKind::Cover { .. } => true,
+            _ => !inputs.is_empty(),
+        };
+        if !arity_ok {
+            return Err(invalid());
+        }
+
+        Ok(match &gate.kind {
+            GateKind::Buf { delay } => {
+                if !(1..=4).contains(delay) {
+                    return Err(invalid());
+                }
+                let repeater = CRepeater::with_delay(*delay, false);
+                vec![Source::Node(self.block(CBlock::Repeater(repeater), inputs))]
+            }
+            GateKind::Latch { init } => {
+                let repeater = CRepeater::with_delay(1, *init);
+                vec![Source::Node(self.block(CBlock::Repeater(repeater), inputs))]
+            }
+            GateKind::Not | GateKind::Nor => vec![self.torch(inputs)],
+            GateKind::Or => inputs,
+            GateKind::And => {
+                let inverted = gate.inputs.iter().map(|s| self.not(s)).collect_vec();
+                vec![self.torch(inverted)]
+            }
+            GateKind::Nand => gate.inputs.iter().map(|s| self.not(s)).collect(),
+            GateKind::Xor | GateKind::Xnor => {
+                let (a, b) = (gate.inputs[0].as_str(), gate.inputs[1].as_str());
+                let (not_a, not_b) = (self.not(a), self.not(b));
+                let xor = vec![
+                    self.torch([not_a, Source::Signal(b)]),
+                    self.torch([Source::Signal(a), not_b]),
+                ];
+                if gate.kind == GateKind::Xor {
+                    xor
+                } else {
+                    vec![self.torch(xor)]
+                }
+            }
+            GateKind::Cover { cubes, value } => {
+                let mut on = vec![];
+                for cube in cubes {
+                    if cube.len() != gate.inputs.len() {
+                        return Err(invalid());
+                    }
+                    // The torch of a cube is lit while none of its literals are false.
+                    let mut literals = vec![];
+                    for (c, signal) in cube.chars().zip(&gate.inputs) {
+                        match c {
+                            '1' => literals.push(self.not(signal)),
+                            '0' => literals.push(Source::Signal(signal)),
+                            '-' => {}
+                            _ => return Err(invalid()),
+                        }
+                    }
+                    if literals.is_empty() {
+                        let block = CBlock::RedstoneBlock(CRedstoneBlock::default());
+                        on.push(Source::Node(self.block(block, [])));
+                    } else {
+                        on.push(self.torch(literals));
+                    }
+                }
+                if *value {
+                    on
+                } else {
+                    vec![self.torch(on)]
+                }
+            }
+        })
+    }
+
+    /// Returns the blocks that are joined by a wire to form the signal.
+    fn resolve(
+        &self,
+        signal: &'a str,
+        resolved: &mut HashMap<&'a str, Vec<NodeIndex>>,
+        visiting: &mut HashSet<&'a str>,
+    ) -> Result<Vec<NodeIndex>, NetlistError> {
+        if let Some(nodes) = resolved.get(signal) {
+            return Ok(nodes.clone());
+        }
+        let sources = self
+            .signals
+            .get(signal)
+            .ok_or_else(|| NetlistError::Undefined(signal.to_string()))?;
+        if !visiting.insert(signal) {
+            return Err(NetlistError::WireLoop(signal.to_string()));
+        }
+
+        let mut nodes = vec![];
+        for &source in sources {
+            match source {
+                Source::Node(idx) => nodes.push(idx),
+                Source::Signal(s) => nodes.extend(self.resolve(s, resolved, visiting)?),
+            }
+        }
+        let nodes = nodes.into_iter().unique().collect_vec();
+
+        visiting.remove(signal);
+        resolved.insert(signal, nodes.clone());
+        Ok(nodes)
+    }
+}
+
+/// Constructs the unpruned graph of the blocks that implement the netlist. Blocks have no
+/// position, and start in the state the circuit settles in while every input is off.
+pub fn cblock_graph(netlist: &Netlist) -> Result<(CBlockGraph, Provenance), NetlistError> {
+    let mut builder = Builder {
+        cblocks: CBlockGraph::new(),
+        signals: HashMap::new(),
+        inputs: vec![],
+        inverted: HashMap::new(),
+    };
+
+    let drive = |builder: &Builder<'_>, signal: &str| {
+        if builder.signals.contains_key(signal) {
+            return Err(NetlistError::MultipleDrivers(signal.to_string()));
+        }
+        Ok(())
+    };
+    for name in &netlist.inputs {
+        drive(&builder, name)?;
+        let trigger = CBlock::Trigger(CTrigger { name: name.clone() });
+        let idx = builder.block(trigger, []);
+        builder.signals.insert(name, vec![Source::Node(idx)]);
+    }
+    // First node of the blocks of every gate, with the signal the gate drives.
+    let mut gate_nodes = vec![];
+    for gate in &netlist.gates {
+        drive(&builder, &gate.output)?;
+        gate_nodes.push((builder.cblocks.node_count(), gate.output.as_str()));
+        let sources = builder.gate(gate)?;
+        builder.signals.insert(&gate.output, sources);
+    }
+    for name in &netlist.outputs {
+        let probe = CBlock::Probe(CProbe { name: name.clone() });
+        builder.block(probe, [Source::Signal(name)]);
+    }
+
+    let mut resolved = HashMap::new();
+    for (source, target) in builder.inputs.clone() {
+        let sources = match source {
+            Source::Node(idx) => vec![idx],
+            Source::Signal(s) => builder.resolve(s, &mut resolved, &mut HashSet::new())?,
+        };
+        for idx in sources {
+            builder.cblocks.update_edge(idx, target, Edge::Rear(0));
+        }
+    }
+
+    let mut cblocks = builder.cblocks;
+    if let Some(idx) = settle(&mut cblocks) {
+        let (_, signal) = gate_nodes
+            .iter()
+            .rev()
+            .find(|&&(first, _)| first <= idx.index())
+            .unwrap();
+        return Err(NetlistError::Unsettled(signal.to_string()));
+    }
+    Ok((cblocks, Provenance::default()))
+}
+
+/// Sets the state of every torch and repeater to what it settles in while every input is off,
+/// updating blocks in place until none change, which also settles latches made of gates.
+/// Returns a block that still changes if the blocks do not settle.
+fn settle(cblocks: &mut CBlockGraph) -> Option<NodeIndex> {
+    let powered = |cblocks: &CBlockGraph, idx: NodeIndex| match &cblocks[idx] {
+        CBlock::Torch(t) => t.is_lit(),
+        CBlock::Repeater(r) => r.is_powered(),
+        CBlock::RedstoneBlock(_) => true,
+        _ => false,
+    };
+
+    let mut changed = None;
+    for _ in 0..=cblocks.node_count() {
+        changed = None;
+        for idx in cblocks.node_indices().collect_vec() {
+            let input = cblocks
+                .neighbors_directed(idx, Incoming)
+                .any(|n| powered(cblocks, n));
+            match &cblocks[idx] {
+                CBlock::Torch(t) if t.is_lit() == input => {
+                    cblocks[idx] = CBlock::Torch(CTorch::with_lit(!input));
+                    changed = Some(idx);
+                }
+                CBlock::Repeater(r) if r.is_powered() != input => {
+                    cblocks[idx] = CBlock::Repeater(CRepeater::with_delay(r.delay(), input));
+                    changed = Some(idx);
+                }
+                _ => {}
+            }
+        }
+        if changed.is_none() {
+            break;
+        }
+    }
+
+    changed
+}
+
+impl World {
+    /// Builds the netlist out of redstone blocks, running only the given pruning passes.
+    pub fn from_netlist(netlist: &Netlist, options: &PruneOptions) -> Result<World, NetlistError> {
+        let (cblocks, provenance) = cblock_graph(netlist)?;
+        Ok(World::from_cblock_graph(cblocks, provenance, options))
+    }
+}
@@ -0,0 +1,123 @@
+use crate::blocks::{BlockState, OutputPower, RuntimeState};
+use crate::world::World;
+use petgraph::prelude::NodeIndex;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// A cycle in the runtime state of a `World`, such as a clock or a feedback loop.
+#[derive(Debug, Clone)]
+pub struct Oscillation {
+    /// Tick at which the world first entered the cycle.
+    pub start: usize,
+
+    /// Number of ticks after which the runtime state repeats.
+    pub period: usize,
+
+    /// Nodes of `World::cblocks` whose output changes during the cycle.
+    pub nodes: Vec<NodeIndex>,
+
+    /// Positions of the blocks that the nodes that change during the cycle stand for.
+    pub positions: Vec<(isize, isize, isize)>,
+}
+
+impl World {
+    /// Steps the world until its runtime state repeats, for at most `max_ticks` ticks.
+    /// Returns `None` if the world became stable or no cycle was found in time.
+    pub fn detect_oscillation(&mut self, max_ticks: usize) -> Option<Oscillation> {
+        let mut seen: HashMap<u64, usize> = HashMap::new();
+
+        for _ in 0..max_ticks {
+            if self.is_stable() {
+                return None;
+            }
+
+            let hash = self.state_hash();
+            if let Some(&start) = seen.get(&hash) {
+                return Some(self.oscillation(start, self.tick_counter - start));
+            }
+            seen.insert(hash, self.tick_counter);
+
+            self.step();
+        }
+
+        None
+    }
+
+    /// Steps through one period of a detected cycle, collecting the nodes that change.
+    fn oscillation(&mut self, start: usize, period: usize) -> Oscillation {
+        let initial: Vec<u8> = self
+            .blocks
+            .nodes
+            .iter()
+            .map(|n| n.weight.output_power())
+            .collect();
+        let mut toggled = vec![false; initial.len()];
+
+        for _ in 0..period {
+            self.step();
+            for ((n, &p), t) in self.blocks.nodes.iter().zip(&initial).zip(&mut toggled) {
+                *t |= n.weight.output_power() != p;
+            }
+        }
+
+        let nodes: Vec<NodeIndex> = self
+            .blocks
+            .indices
+            .iter()
+            .zip(toggled)
+            .filter(|(_, t)| *t)
+            .map(|(&idx, _)| idx)
+            .collect();
+        let positions = nodes
+            .iter()
+            .flat_map(|&idx| self.provenance.node(idx).iter().copied())
+            .collect();
+
+        Oscillation {
+            start,
+            period,
+            nodes,
+            positions,
+        }
+    }
+
+    /// Hashes the runtime state of the world, ignoring the global tick counter.
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for n in &self.blocks.nodes {
+            let mut state = n.weight.state();
+            // `last_update` only stores a past tick, so it does not influence future ticks, and
+            // the scheduled flips of a delay line only matter relative to the current tick.
+            match &mut state {
+                BlockState::Repeater { last_update, .. }
+                | BlockState::Buffer { last_update, .. } => *last_update = 0,
+                BlockState::DelayLine {
+                    stages,
+                    last_update,
+                    ..
+                } => {
+                    *last_update = 0;
+                    for stage in stages {
+                        stage.flip = stage.flip.map(|t| t.wrapping_sub(self.tick_counter));
+                    }
+                }
+                _ => {}
+            }
+            state.hash(&mut hasher);
+        }
+        for list in [
+            &self.updatable,
+            &self.tick_updatable.up,
+            &self.tick_updatable.down,
+        ] {
+            list.len().hash(&mut hasher);
+            for &n in list {
+                std::ptr::hash(n, &mut hasher);
+            }
+        }
+
+        hasher.finish()
+    }
+}
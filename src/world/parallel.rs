@@ -0,0 +1,477 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::BlockState;
+use crate::world::compiled::{
+    repeater_levels, CompileError, CompiledGraph, Csr, NodeKind, NodeState,
+};
+use crate::world::World;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize};
+use std::thread;
+
+/// Default minimum number of nodes updated in one phase before the work is split over threads.
+const PARALLEL_THRESHOLD: usize = 256;
+
+/// Number of regions the graph is partitioned into for every thread.
+const REGIONS_PER_THREAD: usize = 4;
+
+/// Runtime fields of a node, shared between the threads.
+struct AtomicNodeState {
+    /// Output power, or the signal of a probe.
+    output: AtomicU8,
+    /// Next signal of a comparator, or the next power of a repeater.
+    next: AtomicU8,
+    locking_signal: AtomicBool,
+    count: AtomicU8,
+    on_inputs: AtomicU8,
+    last_update: AtomicUsize,
+}
+
+impl From<NodeState> for AtomicNodeState {
+    fn from(state: NodeState) -> Self {
+        AtomicNodeState {
+            output: state.output.into(),
+            next: state.next.into(),
+            locking_signal: state.locking_signal.into(),
+            count: state.count.into(),
+            on_inputs: state.on_inputs.into(),
+            last_update: state.last_update.into(),
+        }
+    }
+}
+
+impl AtomicNodeState {
+    fn load(&self) -> NodeState {
+        NodeState {
+            output: self.output.load(Relaxed),
+            next: self.next.load(Relaxed),
+            locking_signal: self.locking_signal.load(Relaxed),
+            count: self.count.load(Relaxed),
+            on_inputs: self.on_inputs.load(Relaxed),
+            last_update: self.last_update.load(Relaxed),
+        }
+    }
+}
+
+/// A `World` whose ticks are evaluated by several threads.
+///
+/// The graph is partitioned into regions of nodes that are close to each other, and the
+/// updates of every region are done by one thread. Repeaters that update other repeaters within a
+/// tick are ordered in levels, and the updates of every level are merged before the next level
+/// starts, so the result does not depend on the number of threads.
+pub struct ParallelWorld {
+    kinds: Vec<NodeKind>,
+    outgoing: Csr,
+    incoming_rear: Csr,
+    incoming_side: Csr,
+    nodes: Vec<AtomicNodeState>,
+
+    /// Level of every node, a repeater is only updated after the repeaters that output to it.
+    levels: Vec<u32>,
+
+    /// Whether a level contains repeaters that output to each other in a cycle. The updates of such
+    /// levels are done on one thread, and repeated until no repeater of the level is pushed anymore.
+    cyclic: Vec<bool>,
+
+    /// Number of consecutive nodes in a region.
+    region_size: usize,
+    threads: usize,
+
+    /// Minimum number of nodes updated in one phase before the work is split over threads.
+    threshold: usize,
+
+    /// Index in the `CompiledGraph` of every node.
+    order: Vec<u32>,
+
+    triggers: Vec<(String, u32)>,
+    probes: Vec<(String, u32)>,
+
+    /// Nodes that are updated during the next tick.
+    pending: Vec<u32>,
+
+    /// Global tick counter.
+    tick_counter: usize,
+}
+
+/// Returns the nodes in breadth-first order, so that neighbouring nodes get nearby indices.
+fn bfs_order(graph: &CompiledGraph) -> Vec<u32> {
+    let mut visited = vec![false; graph.len()];
+    let mut order = Vec::with_capacity(graph.len());
+    let mut queue = VecDeque::new();
+
+    for start in 0..graph.len() as u32 {
+        if visited[start as usize] {
+            continue;
+        }
+        visited[start as usize] = true;
+        queue.push_back(start);
+
+        while let Some(n) = queue.pop_front() {
+            order.push(n);
+            let neighbours = graph
+                .outgoing
+                .edges(n)
+                .chain(graph.incoming_rear.edges(n))
+                .chain(graph.incoming_side.edges(n));
+            for (m, _) in neighbours {
+                if !visited[m as usize] {
+                    visited[m as usize] = true;
+                    queue.push_back(m);
+                }
+            }
+        }
+    }
+
+    order
+}
+
+/// Returns `csr` with its nodes renumbered from `order[i]` to `i`.
+fn renumber(csr: &Csr, order: &[u32], new_index: &[u32]) -> Csr {
+    let mut result = Csr {
+        offsets: vec![0],
+        ..Default::default()
+    };
+    for &n in order {
+        for (m, w) in csr.edges(n) {
+            result.nodes.push(new_index[m as usize]);
+            result.weights.push(w);
+        }
+        result.offsets.push(result.nodes.len() as u32);
+    }
+    result
+}
+
+/// Returns the perceived power of every edge in `edges`.
+fn inputs<'a>(
+    nodes: &'a [AtomicNodeState],
+    edges: impl Iterator<Item = (u32, u8)> + 'a,
+) -> impl Iterator<Item = u8> + 'a {
+    edges.map(|(n, w)| nodes[n as usize].output.load(Relaxed).saturating_sub(w))
+}
+
+impl ParallelWorld {
+    /// Creates a `ParallelWorld` in the current state of `world`, which uses `threads` threads.
+    /// Fails if the world cannot be compiled.
+    pub fn new(world: &World, threads: usize) -> Result<Self, CompileError> {
+        let graph = CompiledGraph::new(world)?;
+        let threads = threads.max(1);
+
+        let order = bfs_order(&graph);
+        let mut new_index = vec![0; graph.len()];
+        for (i, &n) in order.iter().enumerate() {
+            new_index[n as usize] = i as u32;
+        }
+        let kinds: Vec<NodeKind> = order.iter().map(|&n| graph.kinds[n as usize]).collect();
+        let outgoing = renumber(&graph.outgoing, &order, &new_index);
+
+        let (levels, cyclic) = repeater_levels(&kinds, &outgoing);
+
+        let states: Vec<NodeState> = graph.node_states().collect();
+        let nodes = order.iter().map(|&n| states[n as usize].into()).collect();
+
+        let renumber_names = |list: &[(String, u32)]| {
+            list.iter()
+                .map(|(s, n)| (s.clone(), new_index[*n as usize]))
+                .collect()
+        };
+
+        Ok(ParallelWorld {
+            levels,
+            cyclic,
+            region_size: kinds.len().div_ceil(threads * REGIONS_PER_THREAD).max(1),
+            threads,
+            threshold: PARALLEL_THRESHOLD,
+            incoming_rear: renumber(&graph.incoming_rear, &order, &new_index),
+            incoming_side: renumber(&graph.incoming_side, &order, &new_index),
+            outgoing,
+            kinds,
+            nodes,
+            triggers: renumber_names(&graph.triggers),
+            probes: renumber_names(&graph.probes),
+            pending: graph
+                .pending
+                .iter()
+                .map(|&n| new_index[n as usize])
+                .collect(),
+            order,
+            tick_counter: world.tick_counter(),
+        })
+    }
+
+    /// Sets the minimum number of nodes updated in one phase before the work is split over threads.
+    /// Smaller phases are done on the calling thread, as spawning threads would take longer.
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Returns whether the probe is currently powered.
+    pub fn get_probe(&self, name: &str) -> Option<bool> {
+        self.probes
+            .iter()
+            .find(|(s, _)| s == name)
+            .map(|(_, n)| self.nodes[*n as usize].output.load(Relaxed) > 0)
+    }
+
+    /// Powers or unpowers every trigger with the given name until it is set again.
+    /// Returns `false` if there is no such trigger.
+    pub fn set_trigger(&mut self, name: &str, powered: bool) -> bool {
+        let mut found = false;
+        for (_, n) in self.triggers.iter().filter(|(s, _)| s == name) {
+            found = true;
+            let output = if powered { 15 } else { 0 };
+            if self.nodes[*n as usize].output.swap(output, Relaxed) != output {
+                self.pending.extend(self.outgoing.edges(*n).map(|(m, _)| m));
+            }
+        }
+        found
+    }
+
+    /// Powers all triggers for a single tick.
+    pub fn step_with_trigger(&mut self) {
+        let names: Vec<String> = self.triggers.iter().map(|(s, _)| s.clone()).collect();
+        for name in &names {
+            self.set_trigger(name, true);
+        }
+        self.step();
+        for name in &names {
+            self.set_trigger(name, false);
+        }
+    }
+
+    /// Returns the number of ticks simulated so far.
+    pub fn tick_counter(&self) -> usize {
+        self.tick_counter
+    }
+
+    /// Returns the runtime state of every node, in the order of `World::blocks`.
+    pub fn states(&self) -> Vec<BlockState> {
+        let mut states = vec![BlockState::Redstone { signal: false }; self.nodes.len()];
+        for (n, (state, &kind)) in self.nodes.iter().zip(&self.kinds).enumerate() {
+            states[self.order[n] as usize] = state.load().block_state(kind);
+        }
+        states
+    }
+
+    pub fn step(&mut self) {
+        let mut work = vec![Vec::new(); self.cyclic.len()];
+        for n in self.pending.drain(..) {
+            work[self.levels[n as usize] as usize].push(n);
+        }
+
+        // Tick updates, repeaters only push repeaters of the same or a higher level.
+        let mut late = Vec::new();
+        for level in 0..self.cyclic.len() {
+            let mut list = std::mem::take(&mut work[level]);
+            while !list.is_empty() {
+                list.sort_unstable();
+                list.dedup();
+
+                let parallel = !self.cyclic[level];
+                let (pushed, updated) = self.run(&list, parallel, |world, n, pushed, updated| {
+                    if world.update(n, pushed) {
+                        updated.push(n);
+                    }
+                });
+                late.extend(updated);
+
+                list.clear();
+                for n in pushed {
+                    if self.levels[n as usize] as usize == level {
+                        list.push(n);
+                    } else {
+                        work[self.levels[n as usize] as usize].push(n);
+                    }
+                }
+            }
+        }
+
+        // End-of-tick updates
+        late.sort_unstable();
+        late.dedup();
+        let tick = self.tick_counter;
+        let (pushed, _) = self.run(&late, true, |world, n, pushed, _| {
+            world.late_update(n, pushed, tick);
+        });
+        self.pending = pushed;
+
+        self.tick_counter += 1;
+    }
+
+    /// Calls `f` for every node in `list`, splitting the nodes over the threads by region if
+    /// `parallel` is set. Returns the concatenation of the two lists `f` pushes to.
+    fn run(
+        &self,
+        list: &[u32],
+        parallel: bool,
+        f: impl Fn(&ParallelWorld, u32, &mut Vec<u32>, &mut Vec<u32>) + Sync,
+    ) -> (Vec<u32>, Vec<u32>) {
+        if !parallel || self.threads == 1 || list.len() < self.threshold {
+            let mut result = (Vec::new(), Vec::new());
+            for &n in list {
+                f(self, n, &mut result.0, &mut result.1);
+            }
+            return result;
+        }
+
+        let mut buckets = vec![Vec::new(); self.threads];
+        for &n in list {
+            buckets[n as usize / self.region_size % self.threads].push(n);
+        }
+
+        let f = &f;
+        let results: Vec<(Vec<u32>, Vec<u32>)> = thread::scope(|s| {
+            let handles: Vec<_> = buckets
+                .iter()
+                .map(|bucket| {
+                    s.spawn(move || {
+                        let mut result = (Vec::new(), Vec::new());
+                        for &n in bucket {
+                            f(self, n, &mut result.0, &mut result.1);
+                        }
+                        result
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        results
+            .into_iter()
+            .fold((Vec::new(), Vec::new()), |mut acc, (a, b)| {
+                acc.0.extend(a);
+                acc.1.extend(b);
+                acc
+            })
+    }
+
+    /// Updates node `n` like `Updatable::update`. Returns whether it requires an end-of-tick update.
+    fn update(&self, n: u32, pushed: &mut Vec<u32>) -> bool {
+        let state = &self.nodes[n as usize];
+        let rear = || inputs(&self.nodes, self.incoming_rear.edges(n));
+        let side = || inputs(&self.nodes, self.incoming_side.edges(n));
+
+        match self.kinds[n as usize] {
+            NodeKind::Trigger => false,
+            NodeKind::Probe => {
+                let on = rear().any(|p| p > 0);
+                state.output.store(if on { 15 } else { 0 }, Relaxed);
+                false
+            }
+            NodeKind::SRepeater => {
+                let on_inputs = rear().chain(side()).filter(|&p| p > 0).count() as u8;
+                let prev = state.on_inputs.swap(on_inputs, Relaxed);
+                (prev > 0) != (on_inputs > 0)
+            }
+            NodeKind::Comparator { mode, entity_power } => {
+                let rear = rear().max().max(entity_power).unwrap_or(0);
+                let side = side().max().unwrap_or(0);
+                let next = match mode {
+                    ComparatorMode::Compare if side <= rear => rear,
+                    ComparatorMode::Compare => 0,
+                    ComparatorMode::Subtract => rear.saturating_sub(side),
+                };
+                state.next.store(next, Relaxed);
+                state.output.load(Relaxed) != next
+            }
+            NodeKind::Repeater { delay } => {
+                if side().any(|p| p > 0) {
+                    return false;
+                }
+
+                let s_new = rear().any(|p| p > 0);
+                let locked_next_tick = self.incoming_side.edges(n).any(|(m, _)| {
+                    let source = &self.nodes[m as usize];
+                    match self.kinds[m as usize] {
+                        NodeKind::Repeater { .. } => source.locking_signal.load(Relaxed),
+                        NodeKind::Comparator { .. } => source.output.load(Relaxed) > 0,
+                        _ => unreachable!(),
+                    }
+                });
+
+                if locked_next_tick == state.locking_signal.load(Relaxed) {
+                    pushed.extend(
+                        self.outgoing.edges(n).map(|(m, _)| m).filter(|&m| {
+                            matches!(self.kinds[m as usize], NodeKind::Repeater { .. })
+                        }),
+                    );
+                }
+
+                let powered = state.output.load(Relaxed) > 0;
+                let mut next = state.next.load(Relaxed) > 0;
+                let count = state.count.load(Relaxed);
+                if s_new && !next {
+                    // Signal changed upwards: update next signal and reset count.
+                    next = true;
+                    state.count.store(0, Relaxed);
+                } else if !s_new && next && count == 0 {
+                    // Signal changed downward, and is not propagating already: update next signal.
+                    next = false;
+                }
+                state.next.store(next as u8, Relaxed);
+
+                let count = state.count.load(Relaxed);
+                state.locking_signal.store(
+                    if locked_next_tick {
+                        powered
+                    } else if count + 1 == delay {
+                        next
+                    } else {
+                        powered
+                    },
+                    Relaxed,
+                );
+
+                powered != next
+            }
+        }
+    }
+
+    /// Updates node `n` like `Updatable::late_update`, pushing the nodes updated next tick.
+    fn late_update(&self, n: u32, pushed: &mut Vec<u32>, tick_counter: usize) {
+        let state = &self.nodes[n as usize];
+        let (prev, next) = match self.kinds[n as usize] {
+            NodeKind::SRepeater => {
+                let prev = state.output.load(Relaxed);
+                (prev, 15 - prev)
+            }
+            NodeKind::Comparator { .. } => (state.output.load(Relaxed), state.next.load(Relaxed)),
+            NodeKind::Repeater { delay } => {
+                if state.last_update.swap(tick_counter, Relaxed) == tick_counter {
+                    return;
+                }
+                pushed.push(n);
+
+                let count = state.count.load(Relaxed) + 1;
+                if count < delay {
+                    state.count.store(count, Relaxed);
+                    return;
+                }
+                state.count.store(0, Relaxed);
+                // Like `Repeater::late_update`, report a full change even if the power stays the same.
+                if state.locking_signal.load(Relaxed) {
+                    (0, 15)
+                } else {
+                    (15, 0)
+                }
+            }
+            NodeKind::Trigger | NodeKind::Probe => unreachable!(),
+        };
+        state.output.store(next, Relaxed);
+
+        let edges = self.outgoing.edges(n);
+        match (prev, next) {
+            (0, 15) | (15, 0) => pushed.extend(edges.map(|(m, _)| m)),
+            _ if next > prev => pushed.extend(
+                edges
+                    .filter(|(_, w)| (prev..next).contains(w))
+                    .map(|(m, _)| m),
+            ),
+            _ => pushed.extend(
+                edges
+                    .filter(|(_, w)| (next..prev).contains(w))
+                    .map(|(m, _)| m),
+            ),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::world::CBlockGraph;
+use petgraph::prelude::{EdgeIndex, NodeIndex};
+use std::collections::HashMap;
+
+/// Position of a block in the coordinates of the schematic.
+pub type Position = (isize, isize, isize);
+
+/// Blocks of the schematic that the nodes and edges of a pruned `CBlockGraph` stand for.
+#[derive(Clone, Debug, Default)]
+pub struct Provenance {
+    /// Positions of the blocks every node represents, more than one if nodes were merged.
+    pub nodes: HashMap<NodeIndex, Vec<Position>>,
+
+    /// Positions of the dust, solid blocks and folded components every edge passes through,
+    /// in order from its source to its target. Edges between adjacent blocks have no entry.
+    pub edges: HashMap<EdgeIndex, Vec<Position>>,
+}
+
+impl Provenance {
+    /// Returns the positions of the blocks the node represents.
+    pub fn node(&self, idx: NodeIndex) -> &[Position] {
+        self.nodes.get(&idx).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the position of the first block the node represents.
+    pub fn position(&self, idx: NodeIndex) -> Option<Position> {
+        self.node(idx).first().copied()
+    }
+
+    /// Returns the positions of the blocks the edge passes through.
+    pub fn edge(&self, idx: EdgeIndex) -> &[Position] {
+        self.edges.get(&idx).map_or(&[], Vec::as_slice)
+    }
+
+    /// Records that the edge passes through `path`, replacing what was recorded for its index.
+    /// Indices of removed edges are reused by `CBlockGraph::add_edge`, so every new edge must be
+    /// recorded.
+    pub(crate) fn set_edge(&mut self, idx: EdgeIndex, path: Vec<Position>) {
+        if path.is_empty() {
+            self.edges.remove(&idx);
+        } else {
+            self.edges.insert(idx, path);
+        }
+    }
+
+    /// Adds the blocks that `other` represents to `node`.
+    pub(crate) fn merge_nodes(&mut self, node: NodeIndex, other: NodeIndex) {
+        let positions = self.nodes.remove(&other).unwrap_or_default();
+        self.nodes.entry(node).or_default().extend(positions);
+    }
+
+    /// Forgets the nodes and edges that are no longer in the graph.
+    pub(crate) fn retain(&mut self, cblocks: &CBlockGraph) {
+        self.nodes.retain(|&idx, _| cblocks.contains_node(idx));
+        self.edges
+            .retain(|&idx, _| cblocks.edge_weight(idx).is_some());
+    }
+}
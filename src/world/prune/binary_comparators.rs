@@ -0,0 +1,80 @@
+use crate::blocks::binary_comparator::CBinaryComparator;
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::{CBlock, OutputPower};
+use crate::world::CBlockGraph;
+use itertools::Itertools;
+use petgraph::prelude::*;
+
+/// Returns the signal strength the node outputs while it is on, if it only outputs that or nothing.
+fn binary_output(cblocks: &CBlockGraph, idx: NodeIndex) -> Option<u8> {
+    match &cblocks[idx] {
+        CBlock::Trigger(_)
+        | CBlock::Repeater(_)
+        | CBlock::SRepeater(_)
+        | CBlock::DelayLine(_)
+        | CBlock::Buffer(_)
+        | CBlock::RedstoneBlock(_)
+        | CBlock::Torch(_) => Some(15),
+        CBlock::BinaryComparator(c) => Some(c.on()),
+        _ => None,
+    }
+}
+
+/// Returns the binary comparator that behaves the same as the comparator, if every rear input has
+/// the same signal strength while it is on, and every side input either turns the output off or
+/// does not affect it.
+fn binary_comparator(cblocks: &CBlockGraph, idx: NodeIndex) -> Option<CBinaryComparator> {
+    let CBlock::Comparator(c) = &cblocks[idx] else {
+        return None;
+    };
+    if c.contains_entity_power() {
+        return None;
+    }
+
+    let mut rear = None;
+    let mut side = vec![];
+    for e in cblocks.edges_directed(idx, Incoming) {
+        let strength =
+            binary_output(cblocks, e.source())?.saturating_sub(e.weight().strength_loss());
+        if strength == 0 {
+            // The edge never carries any signal.
+            continue;
+        }
+        if e.weight().is_side() {
+            side.push(strength);
+        } else if rear.is_some_and(|r| r != strength) {
+            return None;
+        } else {
+            rear = Some(strength);
+        }
+    }
+
+    // Comparators without rear inputs are left to `prune_constants`.
+    let on = rear?;
+    if c.mode() == ComparatorMode::Subtract && side.iter().any(|&s| s < on) {
+        return None;
+    }
+    if c.output_power() != 0 && c.output_power() != on {
+        return None;
+    }
+    Some(CBinaryComparator::new(c.output_power(), on, c.mode()))
+}
+
+/// Replaces comparators with binary inputs by binary comparators, until no more comparators can be
+/// replaced. Returns the number of replaced comparators.
+pub fn replace_binary_comparators(cblocks: &mut CBlockGraph) -> usize {
+    let mut replaced = 0;
+    loop {
+        let comparators = cblocks
+            .node_indices()
+            .filter_map(|idx| Some((idx, binary_comparator(cblocks, idx)?)))
+            .collect_vec();
+        if comparators.is_empty() {
+            return replaced;
+        }
+        replaced += comparators.len();
+        for (idx, c) in comparators {
+            cblocks[idx] = CBlock::BinaryComparator(c);
+        }
+    }
+}
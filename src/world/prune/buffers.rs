@@ -0,0 +1,111 @@
+use crate::blocks::buffer::{CBuffer, MAX_DELAY};
+use crate::blocks::{CBlock, OutputPower};
+use crate::world::provenance::Provenance;
+use crate::world::CBlockGraph;
+use itertools::Itertools;
+use petgraph::prelude::*;
+
+/// Returns whether the node outputs its input of the previous tick, and whether it inverts it.
+fn stage(cblocks: &CBlockGraph, idx: NodeIndex) -> Option<(bool, bool)> {
+    match &cblocks[idx] {
+        CBlock::Torch(t) => Some((t.output_power() > 0, true)),
+        CBlock::SRepeater(r) => Some((r.output_power() > 0, false)),
+        _ => None,
+    }
+}
+
+/// Returns the edge to the next block of a chain, if the torch or simple repeater only powers a
+/// torch or simple repeater that is only powered by it.
+fn next_in_chain(cblocks: &CBlockGraph, idx: NodeIndex) -> Option<(EdgeIndex, NodeIndex)> {
+    stage(cblocks, idx)?;
+    let (edge,) = cblocks.edges_directed(idx, Outgoing).collect_tuple()?;
+    let next = edge.target();
+    if edge.weight().is_side()
+        || edge.weight().strength_loss() >= 15
+        || next == idx
+        || stage(cblocks, next).is_none()
+        || cblocks.edges_directed(next, Incoming).count() != 1
+    {
+        return None;
+    }
+    Some((edge.id(), next))
+}
+
+/// Replaces chains of at least two torches and simple repeaters, where every block only powers the
+/// next one, by a single `Buffer`. Pairs of torches that cancel each other out become a buffer
+/// that does not invert. Returns the number of buffers.
+pub fn prune_buffers(cblocks: &mut CBlockGraph, provenance: &mut Provenance) -> usize {
+    let heads = cblocks
+        .node_indices()
+        .filter(|&idx| next_in_chain(cblocks, idx).is_some())
+        .filter(|&idx| {
+            !cblocks
+                .neighbors_directed(idx, Incoming)
+                .any(|prev| next_in_chain(cblocks, prev).is_some_and(|(_, n)| n == idx))
+        })
+        .collect_vec();
+
+    let mut buffers = 0;
+    for head in heads {
+        let mut chain = vec![head];
+        while let Some((_, next)) = next_in_chain(cblocks, *chain.last().unwrap()) {
+            chain.push(next);
+        }
+
+        // The output of a buffer cannot lock repeaters, so the blocks that do stay.
+        let locks_repeater = |idx: NodeIndex| {
+            cblocks
+                .edges_directed(idx, Outgoing)
+                .any(|e| e.weight().is_side() && matches!(cblocks[e.target()], CBlock::Repeater(_)))
+        };
+        if locks_repeater(*chain.last().unwrap()) {
+            chain.pop();
+        }
+
+        for part in chain.chunks(MAX_DELAY).filter(|part| part.len() >= 2) {
+            collapse(cblocks, provenance, part);
+            buffers += 1;
+        }
+    }
+
+    buffers
+}
+
+/// Replaces the chain by a buffer in place of its first block.
+fn collapse(cblocks: &mut CBlockGraph, provenance: &mut Provenance, chain: &[NodeIndex]) {
+    let stages = chain
+        .iter()
+        .map(|&idx| stage(cblocks, idx).unwrap())
+        .collect_vec();
+    let links = chain
+        .iter()
+        .filter_map(|&idx| next_in_chain(cblocks, idx))
+        .map(|(edge, _)| edge)
+        .take(chain.len() - 1)
+        .collect_vec();
+    let positions = chain
+        .iter()
+        .map(|&idx| provenance.node(idx))
+        .interleave(links.iter().map(|&e| provenance.edge(e)))
+        .flatten()
+        .copied()
+        .collect_vec();
+
+    let (head, tail) = (chain[0], chain[chain.len() - 1]);
+    for (edge, target, weight) in cblocks
+        .edges_directed(tail, Outgoing)
+        .map(|e| (e.id(), e.target(), *e.weight()))
+        .collect_vec()
+    {
+        let path = provenance.edge(edge).to_vec();
+        let new = cblocks.add_edge(head, target, weight);
+        provenance.set_edge(new, path);
+    }
+    for &idx in &chain[1..] {
+        cblocks.remove_node(idx);
+        provenance.nodes.remove(&idx);
+    }
+
+    cblocks[head] = CBlock::Buffer(CBuffer::new(&stages));
+    provenance.nodes.insert(head, positions);
+}
@@ -1,12 +1,32 @@
+use crate::blocks::comparator::ComparatorMode;
 use crate::blocks::redstone_block::CRedstoneBlock;
-use crate::blocks::CBlock;
+use crate::blocks::{CBlock, OutputPower};
+use crate::world::edge::Edge;
+use crate::world::provenance::Provenance;
 use crate::world::CBlockGraph;
 use itertools::Itertools;
 use petgraph::prelude::*;
+use petgraph::stable_graph::EdgeReference;
 use petgraph::{Incoming, Outgoing};
 
-pub fn prune_constants(cblocks: &mut CBlockGraph) {
-    prune_torches(cblocks);
+/// Folds blocks with a constant output into redstone blocks, until no more blocks can be folded.
+/// Returns the number of folded blocks.
+pub fn prune_constants(cblocks: &mut CBlockGraph, provenance: &mut Provenance) -> usize {
+    let mut folded = 0;
+    loop {
+        let round = prune_torches(cblocks)
+            + prune_gates(cblocks)
+            + propagate_redstone_blocks(cblocks, provenance);
+        if round == 0 {
+            return folded;
+        }
+        folded += round;
+    }
+}
+
+/// Folds the simple repeaters and torches that redstone blocks power.
+fn propagate_redstone_blocks(cblocks: &mut CBlockGraph, provenance: &mut Provenance) -> usize {
+    let mut folded = 0;
 
     for rblock in cblocks
         .node_indices()
@@ -20,7 +40,8 @@ pub fn prune_constants(cblocks: &mut CBlockGraph) {
             .collect_vec();
 
         while let Some(e) = todo.pop() {
-            if cblocks[e].is_side() {
+            // The edge is gone if a duplicate edge to the same node removed that node already.
+            if cblocks.edge_weight(e).is_none_or(|e| e.is_side()) {
                 continue;
             }
             let nb = cblocks.edge_endpoints(e).unwrap().1;
@@ -32,19 +53,32 @@ pub fn prune_constants(cblocks: &mut CBlockGraph) {
                         .collect_vec()
                         .into_iter()
                         .for_each(|nb2| {
-                            todo.push(cblocks.add_edge(
+                            let path = [
+                                provenance.edge(e),
+                                provenance.node(nb),
+                                provenance.edge(nb2),
+                            ]
+                            .concat();
+                            let new = cblocks.add_edge(
                                 rblock,
                                 cblocks.edge_endpoints(nb2).unwrap().1,
                                 cblocks[nb2],
-                            ));
+                            );
+                            provenance.set_edge(new, path);
+                            todo.push(new);
                         });
                     cblocks.remove_node(nb);
+                    folded += 1;
                 }
                 CBlock::Torch(_) => {
                     cblocks.remove_node(nb);
+                    folded += 1;
                 }
                 CBlock::Comparator(_) => {}
+                CBlock::BinaryComparator(_) => {}
                 CBlock::Repeater(_) => {}
+                CBlock::DelayLine(_) => {}
+                CBlock::Buffer(_) => {}
 
                 CBlock::Redstone(_)
                 | CBlock::SolidWeak(_)
@@ -55,9 +89,12 @@ pub fn prune_constants(cblocks: &mut CBlockGraph) {
             }
         }
     }
+
+    folded
 }
 
-fn prune_torches(cblocks: &mut CBlockGraph) {
+fn prune_torches(cblocks: &mut CBlockGraph) -> usize {
+    let mut folded = 0;
     for idx in cblocks.node_indices().collect_vec() {
         if !matches!(cblocks[idx], CBlock::Torch(_)) {
             continue;
@@ -68,5 +105,99 @@ fn prune_torches(cblocks: &mut CBlockGraph) {
         }
 
         cblocks[idx] = CBlock::RedstoneBlock(CRedstoneBlock::default());
+        folded += 1;
+    }
+    folded
+}
+
+/// Returns the output of the repeater or comparator if it can never change, because it is locked
+/// by a redstone block or all its inputs are redstone blocks. The output must be the current one,
+/// so blocks that are still settling are not folded.
+fn constant_output(cblocks: &CBlockGraph, idx: NodeIndex) -> Option<u8> {
+    let strength = |e: EdgeReference<Edge>| match cblocks[e.source()] {
+        CBlock::RedstoneBlock(_) => Some(15u8.saturating_sub(e.weight().strength_loss())),
+        _ => None,
+    };
+    let inputs = |side: bool| {
+        cblocks
+            .edges_directed(idx, Incoming)
+            .filter(move |e| e.weight().is_side() == side)
+            .map(strength)
+    };
+
+    let output = match &cblocks[idx] {
+        CBlock::Repeater(r) => {
+            // A repeater locked by a redstone block keeps its output forever.
+            if inputs(true).any(|s| s.is_some_and(|s| s > 0)) {
+                return Some(r.output_power());
+            }
+            inputs(true).collect::<Option<Vec<_>>>()?;
+            let rear = inputs(false).collect::<Option<Vec<_>>>()?;
+            if rear.into_iter().any(|s| s > 0) {
+                15
+            } else {
+                0
+            }
+        }
+        CBlock::Comparator(c) => {
+            let rear = inputs(false)
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .max()
+                .max(c.entity_power())
+                .unwrap_or(0);
+            let side = inputs(true)
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .max()
+                .unwrap_or(0);
+            match c.mode() {
+                ComparatorMode::Compare if side <= rear => rear,
+                ComparatorMode::Compare => 0,
+                ComparatorMode::Subtract => rear.saturating_sub(side),
+            }
+        }
+        _ => return None,
+    };
+
+    (output == cblocks[idx].output_power()).then_some(output)
+}
+
+/// Folds repeaters and comparators with a constant output. Blocks that are always off are
+/// removed, others become redstone blocks that lose the missing signal strength over their edges.
+fn prune_gates(cblocks: &mut CBlockGraph) -> usize {
+    let mut folded = 0;
+    for idx in cblocks.node_indices().collect_vec() {
+        let Some(output) = constant_output(cblocks, idx) else {
+            continue;
+        };
+        folded += 1;
+
+        if output == 0 {
+            cblocks.remove_node(idx);
+            continue;
+        }
+
+        for e in cblocks
+            .edges_directed(idx, Incoming)
+            .map(|e| e.id())
+            .collect_vec()
+        {
+            cblocks.remove_edge(e);
+        }
+        for e in cblocks
+            .edges_directed(idx, Outgoing)
+            .map(|e| e.id())
+            .collect_vec()
+        {
+            let weight = Edge::Rear(15 - output) + &cblocks[e];
+            if weight.strength_loss() >= 15 {
+                cblocks.remove_edge(e);
+            } else {
+                cblocks[e] = weight;
+            }
+        }
+        cblocks[idx] = CBlock::RedstoneBlock(CRedstoneBlock::default());
     }
+    folded
 }
@@ -1,4 +1,4 @@
-use crate::blocks::CBlock;
+use crate::blocks::{CBlock, OutputPower};
 use crate::world::CBlockGraph;
 use petgraph::{Incoming, Outgoing};
 
@@ -22,8 +22,17 @@ pub fn prune_dead_nodes(cblocks: &mut CBlockGraph) {
                 v.is_powered() && blocks.neighbors_directed(y, Outgoing).count() > 0
             }
             CBlock::SRepeater(_) => false,
+            CBlock::DelayLine(d) => {
+                d.is_powered() && blocks.neighbors_directed(y, Outgoing).count() > 0
+            }
+            CBlock::Buffer(b) => {
+                b.is_powered() && blocks.neighbors_directed(y, Outgoing).count() > 0
+            }
             // retain torches with outputs (can be used as redstone blocks)
             CBlock::Torch(_) => blocks.neighbors_directed(y, Outgoing).count() > 0,
+            CBlock::BinaryComparator(c) => {
+                c.output_power() > 0 && blocks.neighbors_directed(y, Outgoing).count() > 0
+            }
             CBlock::Comparator(c) => {
                 c.contains_entity_power() && blocks.neighbors_directed(y, Outgoing).count() > 0
             }
@@ -0,0 +1,107 @@
+use crate::blocks::delay_line::CDelayLine;
+use crate::blocks::CBlock;
+use crate::world::provenance::Provenance;
+use crate::world::CBlockGraph;
+use itertools::Itertools;
+use petgraph::prelude::*;
+
+/// Returns whether the node is a repeater that cannot be locked.
+fn is_unlockable_repeater(cblocks: &CBlockGraph, idx: NodeIndex) -> bool {
+    matches!(cblocks[idx], CBlock::Repeater(_))
+        && !cblocks
+            .edges_directed(idx, Incoming)
+            .any(|e| e.weight().is_side())
+}
+
+/// Returns the edge to the next repeater of a chain, if the repeater only powers a repeater that is
+/// only powered by it.
+fn next_in_chain(cblocks: &CBlockGraph, idx: NodeIndex) -> Option<(EdgeIndex, NodeIndex)> {
+    if !is_unlockable_repeater(cblocks, idx) {
+        return None;
+    }
+    let (edge,) = cblocks.edges_directed(idx, Outgoing).collect_tuple()?;
+    let next = edge.target();
+    if edge.weight().is_side()
+        || edge.weight().strength_loss() >= 15
+        || next == idx
+        || !is_unlockable_repeater(cblocks, next)
+        || cblocks.edges_directed(next, Incoming).count() != 1
+    {
+        return None;
+    }
+    Some((edge.id(), next))
+}
+
+/// Replaces chains of at least two repeaters that cannot be locked, where every repeater only
+/// powers the next one, by a single `DelayLine`. Returns the number of delay lines.
+pub fn prune_delay_lines(cblocks: &mut CBlockGraph, provenance: &mut Provenance) -> usize {
+    let heads = cblocks
+        .node_indices()
+        .filter(|&idx| next_in_chain(cblocks, idx).is_some())
+        .filter(|&idx| {
+            !cblocks
+                .neighbors_directed(idx, Incoming)
+                .any(|prev| next_in_chain(cblocks, prev).is_some_and(|(_, n)| n == idx))
+        })
+        .collect_vec();
+
+    let mut lines = 0;
+    for head in heads {
+        let mut chain = vec![head];
+        let mut links = vec![];
+        while let Some((edge, next)) = next_in_chain(cblocks, *chain.last().unwrap()) {
+            chain.push(next);
+            links.push(edge);
+        }
+
+        // The output of a delay line cannot lock repeaters, so the repeaters that do stay.
+        let locks_repeater = |idx: NodeIndex| {
+            cblocks
+                .edges_directed(idx, Outgoing)
+                .any(|e| e.weight().is_side() && matches!(cblocks[e.target()], CBlock::Repeater(_)))
+        };
+        while chain.len() >= 2 && locks_repeater(*chain.last().unwrap()) {
+            chain.pop();
+            links.pop();
+        }
+        if chain.len() < 2 {
+            continue;
+        }
+
+        let repeaters = chain
+            .iter()
+            .map(|&idx| match &cblocks[idx] {
+                CBlock::Repeater(r) => *r,
+                _ => unreachable!(),
+            })
+            .collect_vec();
+        let positions = chain
+            .iter()
+            .map(|&idx| provenance.node(idx))
+            .interleave(links.iter().map(|&e| provenance.edge(e)))
+            .flatten()
+            .copied()
+            .collect_vec();
+
+        let tail = *chain.last().unwrap();
+        for (edge, target, weight) in cblocks
+            .edges_directed(tail, Outgoing)
+            .map(|e| (e.id(), e.target(), *e.weight()))
+            .collect_vec()
+        {
+            let path = provenance.edge(edge).to_vec();
+            let new = cblocks.add_edge(head, target, weight);
+            provenance.set_edge(new, path);
+        }
+        for &idx in &chain[1..] {
+            cblocks.remove_node(idx);
+            provenance.nodes.remove(&idx);
+        }
+
+        cblocks[head] = CBlock::DelayLine(CDelayLine::new(&repeaters));
+        provenance.nodes.insert(head, positions);
+        lines += 1;
+    }
+
+    lines
+}
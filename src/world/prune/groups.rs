@@ -1,10 +1,11 @@
 use crate::blocks::CBlock;
+use crate::world::provenance::Provenance;
 use crate::world::CBlockGraph;
 use itertools::Itertools;
 use petgraph::prelude::*;
 use std::collections::HashMap;
 
-pub fn prune_groups(cblocks: &mut CBlockGraph) {
+pub fn prune_groups(cblocks: &mut CBlockGraph, provenance: &mut Provenance) {
     let mut todo = cblocks
         .node_indices()
         .filter(|i| matches!(cblocks[*i], CBlock::Repeater(_) | CBlock::Torch(_)))
@@ -31,17 +32,21 @@ pub fn prune_groups(cblocks: &mut CBlockGraph) {
         }
 
         if torches.len() > 1 {
-            todo.push(merge_nodes(cblocks, torches.into_iter()));
+            todo.push(merge_nodes(cblocks, provenance, torches.into_iter()));
         }
         for (_, repeaters) in repeaters.into_iter() {
             if repeaters.len() > 1 {
-                todo.push(merge_nodes(cblocks, repeaters.into_iter()));
+                todo.push(merge_nodes(cblocks, provenance, repeaters.into_iter()));
             }
         }
     }
 }
 
-fn merge_nodes(cblocks: &mut CBlockGraph, mut nodes: impl Iterator<Item = NodeIndex>) -> NodeIndex {
+pub(super) fn merge_nodes(
+    cblocks: &mut CBlockGraph,
+    provenance: &mut Provenance,
+    mut nodes: impl Iterator<Item = NodeIndex>,
+) -> NodeIndex {
     let first = nodes.next().unwrap();
     for other in nodes {
         let edges = cblocks
@@ -49,13 +54,15 @@ fn merge_nodes(cblocks: &mut CBlockGraph, mut nodes: impl Iterator<Item = NodeIn
             .map(|e| e.id())
             .collect_vec();
         for edge in edges {
-            cblocks.add_edge(
+            let new = cblocks.add_edge(
                 first,
                 cblocks.edge_endpoints(edge).unwrap().1,
                 cblocks[edge],
             );
+            provenance.set_edge(new, provenance.edge(edge).to_vec());
         }
         cblocks.remove_node(other);
+        provenance.merge_nodes(first, other);
     }
     first
 }
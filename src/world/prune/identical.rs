@@ -0,0 +1,76 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::{CBlock, OutputPower};
+use crate::world::edge::Edge;
+use crate::world::provenance::Provenance;
+use crate::world::prune::duplicate_edges::prune_duplicate_edges;
+use crate::world::prune::groups::merge_nodes;
+use crate::world::CBlockGraph;
+use itertools::Itertools;
+use petgraph::prelude::*;
+use std::collections::HashMap;
+
+/// Parameters and state that nodes must share to be merged.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Kind {
+    Repeater(u8),
+    SRepeater,
+    Torch,
+    Comparator(ComparatorMode, Option<u8>),
+}
+
+/// Kind, output power and incoming edges of a node.
+type Key = (Kind, u8, Vec<(NodeIndex, Edge)>);
+
+/// Merges gates of the same kind, parameters and state that have exactly the same incoming edges,
+/// until no more gates can be merged. Returns the number of merged nodes.
+pub fn prune_identical(
+    cblocks: &mut CBlockGraph,
+    provenance: &mut Provenance,
+    duplicate_edges: bool,
+) -> usize {
+    let mut merged = 0;
+
+    loop {
+        let mut groups: HashMap<Key, Vec<NodeIndex>> = HashMap::new();
+
+        for idx in cblocks.node_indices() {
+            let kind = match &cblocks[idx] {
+                CBlock::Repeater(r) => Kind::Repeater(r.delay()),
+                CBlock::SRepeater(_) => Kind::SRepeater,
+                CBlock::Torch(_) => Kind::Torch,
+                CBlock::Comparator(c) => Kind::Comparator(c.mode(), c.entity_power()),
+                _ => continue,
+            };
+            let incoming = cblocks
+                .edges_directed(idx, Incoming)
+                .map(|e| (e.source(), *e.weight()))
+                .sorted()
+                .collect_vec();
+            groups
+                .entry((kind, cblocks[idx].output_power(), incoming))
+                .or_default()
+                .push(idx);
+        }
+
+        // Merge in a fixed order, so the same schematic always results in the same graph.
+        let nodes = cblocks.node_count();
+        for nodes in groups
+            .into_values()
+            .filter(|g| g.len() > 1)
+            .sorted_by_key(|g| g[0])
+        {
+            merge_nodes(cblocks, provenance, nodes.into_iter());
+        }
+        if nodes == cblocks.node_count() {
+            break;
+        }
+        merged += nodes - cblocks.node_count();
+
+        // Merged nodes output to the same nodes over duplicate edges.
+        if duplicate_edges {
+            prune_duplicate_edges(cblocks);
+        }
+    }
+
+    merged
+}
@@ -1,40 +1,253 @@
+mod binary_comparators;
+mod buffers;
 mod constants;
 mod dead_nodes;
+mod delay_lines;
 mod duplicate_edges;
 mod groups;
+mod identical;
 mod irrelevant;
 mod redstone;
 mod srepeater;
 mod subtractor_edges;
 mod untraversable_edges;
 
+use crate::blocks::CBlock;
+use crate::world::provenance::Provenance;
+use crate::world::prune::binary_comparators::replace_binary_comparators;
+use crate::world::prune::buffers::prune_buffers;
 use crate::world::prune::constants::prune_constants;
 use crate::world::prune::dead_nodes::prune_dead_nodes;
+use crate::world::prune::delay_lines::prune_delay_lines;
 use crate::world::prune::duplicate_edges::prune_duplicate_edges;
 use crate::world::prune::groups::prune_groups;
+use crate::world::prune::identical::prune_identical;
 use crate::world::prune::irrelevant::prune_irrelevant;
 use crate::world::prune::redstone::prune_redstone;
 use crate::world::prune::srepeater::replace_simple_repeaters;
 use crate::world::prune::subtractor_edges::prune_subtractor_edges;
 use crate::world::prune::untraversable_edges::prune_untraversable_edges;
 use crate::world::CBlockGraph;
+use serde::Serialize;
+use std::fmt;
 
-pub fn prune_graph(cblocks: &mut CBlockGraph) {
-    prune_redstone(cblocks);
-    prune_duplicate_edges(cblocks);
-    prune_untraversable_edges(cblocks);
-    prune_groups(cblocks);
-    prune_duplicate_edges(cblocks);
-    prune_irrelevant(cblocks);
-    replace_simple_repeaters(cblocks);
-    prune_subtractor_edges(cblocks);
+/// Which pruning passes to run when constructing a `World`, all of them but `delay_lines` and
+/// `buffers` by default.
+///
+/// `prune_redstone` always runs, since a `World` cannot simulate dust and solid blocks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PruneOptions {
+    pub duplicate_edges: bool,
+    pub untraversable_edges: bool,
+    pub groups: bool,
+    pub irrelevant: bool,
+    pub identical: bool,
+    /// Collapses repeater chains into delay lines, which `CompiledGraph` does not support.
+    pub delay_lines: bool,
+    pub simple_repeaters: bool,
+    pub subtractor_edges: bool,
+    pub constants: bool,
+    pub dead_nodes: bool,
+    pub binary_comparators: bool,
+    /// Collapses chains of torches and simple repeaters into buffers, which `CompiledGraph` does
+    /// not support.
+    pub buffers: bool,
+}
+
+impl Default for PruneOptions {
+    fn default() -> Self {
+        PruneOptions {
+            duplicate_edges: true,
+            untraversable_edges: true,
+            groups: true,
+            irrelevant: true,
+            identical: true,
+            delay_lines: false,
+            simple_repeaters: true,
+            subtractor_edges: true,
+            constants: true,
+            dead_nodes: true,
+            binary_comparators: true,
+            buffers: false,
+        }
+    }
+}
+
+impl PruneOptions {
+    /// Options that only run `prune_redstone`.
+    pub fn none() -> Self {
+        PruneOptions {
+            duplicate_edges: false,
+            untraversable_edges: false,
+            groups: false,
+            irrelevant: false,
+            identical: false,
+            delay_lines: false,
+            simple_repeaters: false,
+            subtractor_edges: false,
+            constants: false,
+            dead_nodes: false,
+            binary_comparators: false,
+            buffers: false,
+        }
+    }
+}
+
+/// Size of the graph before and after a single run of a pruning pass.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PassStats {
+    pub pass: &'static str,
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+    pub edges_before: usize,
+    pub edges_after: usize,
+}
+
+/// Statistics of the pruning passes that ran when constructing a `World`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PruneStats {
+    /// Every run of a pass in order, the constants and dead nodes passes run until nothing changes.
+    pub passes: Vec<PassStats>,
+
+    /// Number of nodes merged into an identical node by `prune_groups`.
+    pub groups_merged: usize,
+
+    /// Number of gates merged into a gate with the same inputs by `prune_identical`.
+    pub identical_merged: usize,
+
+    /// Number of repeater chains replaced by a `DelayLine`.
+    pub delay_lines: usize,
+
+    /// Number of repeaters replaced by an `SRepeater`.
+    pub simple_repeaters: usize,
+
+    /// Number of blocks with a constant output that were folded by `prune_constants`.
+    pub constants_folded: usize,
+
+    /// Number of comparators with binary inputs replaced by a `BinaryComparator`.
+    pub binary_comparators: usize,
+
+    /// Number of chains of torches and simple repeaters replaced by a `Buffer`.
+    pub buffers: usize,
+}
+
+impl PruneStats {
+    /// Runs the pass, recording the size of the graph before and after.
+    fn run<T>(
+        &mut self,
+        pass: &'static str,
+        cblocks: &mut CBlockGraph,
+        f: impl FnOnce(&mut CBlockGraph) -> T,
+    ) -> T {
+        let (nodes_before, edges_before) = (cblocks.node_count(), cblocks.edge_count());
+        let result = f(cblocks);
+        self.passes.push(PassStats {
+            pass,
+            nodes_before,
+            nodes_after: cblocks.node_count(),
+            edges_before,
+            edges_after: cblocks.edge_count(),
+        });
+        result
+    }
+}
+
+impl fmt::Display for PruneStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>14} {:>14} {:>14} {:>14}",
+            "pass", "nodes before", "nodes after", "edges before", "edges after"
+        )?;
+        for p in &self.passes {
+            writeln!(
+                f,
+                "{:<20} {:>14} {:>14} {:>14} {:>14}",
+                p.pass, p.nodes_before, p.nodes_after, p.edges_before, p.edges_after
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "groups merged:       {}", self.groups_merged)?;
+        writeln!(f, "identical merged:    {}", self.identical_merged)?;
+        writeln!(f, "delay lines:         {}", self.delay_lines)?;
+        writeln!(f, "simple repeaters:    {}", self.simple_repeaters)?;
+        writeln!(f, "constants folded:    {}", self.constants_folded)?;
+        writeln!(f, "binary comparators:  {}", self.binary_comparators)?;
+        write!(f, "buffers:             {}", self.buffers)
+    }
+}
+
+/// Runs the pruning passes enabled in `options`.
+///
+/// `prune_redstone` has no option: it replaces dust and solid blocks by direct edges between the
+/// components, and a `World` has no blocks to simulate them with, so the graph can not be
+/// converted without it.
+pub fn prune_graph(
+    cblocks: &mut CBlockGraph,
+    provenance: &mut Provenance,
+    options: &PruneOptions,
+) -> PruneStats {
+    let mut stats = PruneStats::default();
+
+    stats.run("redstone", cblocks, |c| prune_redstone(c, provenance));
+    if options.duplicate_edges {
+        stats.run("duplicate_edges", cblocks, prune_duplicate_edges);
+    }
+    if options.untraversable_edges {
+        stats.run("untraversable_edges", cblocks, prune_untraversable_edges);
+    }
+    if options.groups {
+        let nodes = cblocks.node_count();
+        stats.run("groups", cblocks, |c| prune_groups(c, provenance));
+        stats.groups_merged = nodes - cblocks.node_count();
+        if options.duplicate_edges {
+            stats.run("duplicate_edges", cblocks, prune_duplicate_edges);
+        }
+    }
+    if options.irrelevant {
+        stats.run("irrelevant", cblocks, prune_irrelevant);
+    }
+    if options.identical {
+        stats.identical_merged = stats.run("identical", cblocks, |c| {
+            prune_identical(c, provenance, options.duplicate_edges)
+        });
+    }
+    if options.delay_lines {
+        stats.delay_lines = stats.run("delay_lines", cblocks, |c| prune_delay_lines(c, provenance));
+    }
+    if options.simple_repeaters {
+        stats.run("simple_repeaters", cblocks, replace_simple_repeaters);
+        stats.simple_repeaters = cblocks
+            .node_weights()
+            .filter(|b| matches!(b, CBlock::SRepeater(_)))
+            .count();
+    }
+    if options.subtractor_edges {
+        stats.run("subtractor_edges", cblocks, prune_subtractor_edges);
+    }
 
     loop {
         let nodes = cblocks.node_count();
-        prune_constants(cblocks);
-        prune_dead_nodes(cblocks);
+        if options.constants {
+            stats.constants_folded +=
+                stats.run("constants", cblocks, |c| prune_constants(c, provenance));
+        }
+        if options.dead_nodes {
+            stats.run("dead_nodes", cblocks, prune_dead_nodes);
+        }
         if nodes == cblocks.node_count() {
             break;
         }
     }
+
+    if options.binary_comparators {
+        stats.binary_comparators =
+            stats.run("binary_comparators", cblocks, replace_binary_comparators);
+    }
+    if options.buffers {
+        stats.buffers = stats.run("buffers", cblocks, |c| prune_buffers(c, provenance));
+    }
+
+    provenance.retain(cblocks);
+    stats
 }
@@ -1,11 +1,12 @@
 use crate::blocks::CBlock;
 use crate::world::edge::Edge;
+use crate::world::provenance::Provenance;
 use crate::world::CBlockGraph;
 use itertools::Itertools;
 use petgraph::prelude::*;
 use std::collections::HashSet;
 
-pub fn prune_redstone(cblocks: &mut CBlockGraph) {
+pub fn prune_redstone(cblocks: &mut CBlockGraph, provenance: &mut Provenance) {
     for node in cblocks.node_indices().collect_vec() {
         if matches!(
             cblocks[node],
@@ -14,14 +15,17 @@ pub fn prune_redstone(cblocks: &mut CBlockGraph) {
             continue;
         }
 
-        let mut state = vec![(node, Edge::Rear(0))];
+        let mut state = vec![(node, Edge::Rear(0), None)];
         let mut visited: HashSet<(NodeIndex, bool)> = HashSet::new();
         let mut ends = vec![];
 
+        // Wires that were passed through, with the index of the previous wire on the path.
+        let mut paths: Vec<(Option<usize>, NodeIndex)> = vec![];
+
         loop {
             let mut new_state = vec![];
 
-            for (s, c) in state {
+            for (s, c, path) in state {
                 for nb_edge in cblocks.edges_directed(s, Outgoing) {
                     let nb = nb_edge.target();
 
@@ -35,11 +39,12 @@ pub fn prune_redstone(cblocks: &mut CBlockGraph) {
                         cblocks[nb],
                         CBlock::Redstone(_) | CBlock::SolidStrong(_) | CBlock::SolidWeak(_)
                     ) {
-                        ends.push((nb, c + nb_edge.weight()));
+                        ends.push((nb, c + nb_edge.weight(), path));
                         continue;
                     }
 
-                    new_state.push((nb, c + nb_edge.weight()));
+                    paths.push((path, nb));
+                    new_state.push((nb, c + nb_edge.weight(), Some(paths.len() - 1)));
                 }
             }
 
@@ -50,8 +55,16 @@ pub fn prune_redstone(cblocks: &mut CBlockGraph) {
             state = new_state;
         }
 
-        for (end, i) in ends {
-            cblocks.add_edge(node, end, i);
+        for (end, i, mut path) in ends {
+            let mut positions = vec![];
+            while let Some(p) = path {
+                positions.extend(provenance.node(paths[p].1));
+                path = paths[p].0;
+            }
+            positions.reverse();
+
+            let edge = cblocks.add_edge(node, end, i);
+            provenance.set_edge(edge, positions);
         }
     }
     cblocks.retain_nodes(|blocks, n| {
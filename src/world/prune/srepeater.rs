@@ -34,6 +34,15 @@ pub fn replace_simple_repeaters(cblocks: &mut CBlockGraph) {
                 return;
             }
 
+            // Simple repeaters count every powered input, so they cannot have inputs that never
+            // reach them, which only exist if `prune_untraversable_edges` did not run.
+            if cblocks
+                .edges_directed(idx, Incoming)
+                .any(|edge| edge.weight().strength_loss() >= 15)
+            {
+                return;
+            }
+
             *cblocks.node_weight_mut(idx).unwrap() =
                 CBlock::SRepeater(CSRepeater::with_powered(r.is_powered()));
         });
@@ -0,0 +1,444 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::{CBlock, OutputPower};
+use crate::world::create::cblock_graph;
+use crate::world::edge::Edge;
+use crate::world::schematic::SchemFormat;
+use crate::world::World;
+use nbt::from_gzip_reader;
+use petgraph::prelude::*;
+use petgraph::visit::IntoEdgeReferences;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs::File;
+
+/// Node of the unpruned graph, with its runtime state.
+#[derive(Copy, Clone, Debug)]
+enum Node {
+    /// Redstone dust or a solid block, which passes on the strongest signal it receives.
+    Wire,
+    Trigger {
+        powered: bool,
+    },
+    Probe {
+        powered: bool,
+    },
+    RedstoneBlock,
+    Torch {
+        lit: bool,
+    },
+    Repeater {
+        delay: u8,
+        powered: bool,
+        next_powered: bool,
+        locking_signal: bool,
+        count: u8,
+    },
+    Comparator {
+        mode: ComparatorMode,
+        entity_power: Option<u8>,
+        signal: u8,
+        next_signal: u8,
+    },
+}
+
+/// Slow simulator of the unpruned graph of a schematic, including dust and solid blocks.
+///
+/// Every tick, the signal strength of every wire is recomputed and every component is updated, so
+/// it does not depend on any of the pruning passes or on which nodes the event queues visit.
+/// Used to check a `World` with `differential`.
+pub struct ReferenceWorld {
+    nodes: Vec<Node>,
+
+    /// Incoming edges of every node, with the source node and strength loss.
+    incoming_rear: Vec<Vec<(usize, u8)>>,
+    incoming_side: Vec<Vec<(usize, u8)>>,
+
+    /// Wires that every node outputs to.
+    outgoing_wires: Vec<Vec<usize>>,
+
+    /// Signal strength every node outputs during the current tick.
+    power: Vec<u8>,
+
+    triggers: Vec<(String, usize)>,
+    probes: Vec<(String, usize)>,
+
+    /// Global tick counter, which matches the one of a `World` created from the same schematic.
+    tick_counter: usize,
+}
+
+impl From<File> for ReferenceWorld {
+    fn from(file: File) -> Self {
+        ReferenceWorld::from(from_gzip_reader::<File, SchemFormat>(file).unwrap())
+    }
+}
+
+impl From<SchemFormat> for ReferenceWorld {
+    fn from(format: SchemFormat) -> Self {
+        let (cblocks, _) = cblock_graph(&format);
+
+        let index: HashMap<NodeIndex, usize> = cblocks
+            .node_indices()
+            .enumerate()
+            .map(|(i, n)| (n, i))
+            .collect();
+
+        let mut triggers = Vec::new();
+        let mut probes = Vec::new();
+        let nodes = cblocks
+            .node_indices()
+            .enumerate()
+            .map(|(i, n)| match &cblocks[n] {
+                CBlock::Redstone(_) | CBlock::SolidWeak(_) | CBlock::SolidStrong(_) => Node::Wire,
+                CBlock::Trigger(t) => {
+                    triggers.push((t.name.clone(), i));
+                    Node::Trigger { powered: false }
+                }
+                CBlock::Probe(p) => {
+                    probes.push((p.name.clone(), i));
+                    Node::Probe { powered: false }
+                }
+                CBlock::RedstoneBlock(_) => Node::RedstoneBlock,
+                CBlock::Torch(t) => Node::Torch {
+                    lit: t.output_power() > 0,
+                },
+                CBlock::Repeater(r) => Node::Repeater {
+                    delay: r.delay(),
+                    powered: r.is_powered(),
+                    next_powered: r.is_powered(),
+                    locking_signal: false,
+                    count: 0,
+                },
+                CBlock::Comparator(c) => Node::Comparator {
+                    mode: c.mode(),
+                    entity_power: c.entity_power(),
+                    signal: c.output_power(),
+                    next_signal: c.output_power(),
+                },
+                CBlock::SRepeater(_)
+                | CBlock::DelayLine(_)
+                | CBlock::Buffer(_)
+                | CBlock::BinaryComparator(_) => {
+                    unreachable!("Pruned blocks only exist after pruning.")
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut incoming_rear = vec![vec![]; nodes.len()];
+        let mut incoming_side = vec![vec![]; nodes.len()];
+        let mut outgoing_wires = vec![vec![]; nodes.len()];
+        for e in (&cblocks).edge_references() {
+            let (source, target) = (index[&e.source()], index[&e.target()]);
+            match *e.weight() {
+                Edge::Rear(w) => incoming_rear[target].push((source, w)),
+                Edge::Side(w) => incoming_side[target].push((source, w)),
+            }
+            if matches!(nodes[target], Node::Wire) {
+                outgoing_wires[source].push(target);
+            }
+        }
+
+        let mut world = ReferenceWorld {
+            power: vec![0; nodes.len()],
+            nodes,
+            incoming_rear,
+            incoming_side,
+            outgoing_wires,
+            triggers,
+            probes,
+            tick_counter: 0,
+        };
+
+        // A `World` updates its probes during its first tick, while it is created.
+        world.propagate();
+        for i in 0..world.nodes.len() {
+            if let Node::Probe { .. } = world.nodes[i] {
+                world.nodes[i] = Node::Probe {
+                    powered: world.rear_power(i) > 0,
+                };
+            }
+        }
+        world.tick_counter += 1;
+
+        world
+    }
+}
+
+impl ReferenceWorld {
+    /// Returns whether the probe is currently powered.
+    pub fn get_probe(&self, name: &str) -> Option<bool> {
+        self.probes
+            .iter()
+            .find(|(s, _)| s == name)
+            .map(|&(_, i)| self.is_powered(i))
+    }
+
+    /// Returns `HashMap` from the names of probes to whether they are currently powered.
+    pub fn get_probes(&self) -> HashMap<&str, bool> {
+        self.probes
+            .iter()
+            .map(|(s, i)| (s.as_str(), self.is_powered(*i)))
+            .collect()
+    }
+
+    /// Powers or unpowers every trigger with the given name until it is set again.
+    /// Returns `false` if there is no such trigger.
+    pub fn set_trigger(&mut self, name: &str, powered: bool) -> bool {
+        let mut found = false;
+        for &(ref s, i) in &self.triggers {
+            if s == name {
+                self.nodes[i] = Node::Trigger { powered };
+                found = true;
+            }
+        }
+        found
+    }
+
+    pub fn step_with_trigger(&mut self) {
+        self.toggle_triggers();
+        self.step();
+        self.toggle_triggers();
+    }
+
+    /// Returns the number of ticks simulated so far.
+    pub fn tick_counter(&self) -> usize {
+        self.tick_counter
+    }
+
+    pub fn step(&mut self) {
+        self.propagate();
+
+        // Updates
+        let mut late = vec![false; self.nodes.len()];
+        for (i, late) in late.iter_mut().enumerate() {
+            let rear = self.rear_power(i);
+            let side = self.side_power(i);
+            match &mut self.nodes[i] {
+                Node::Probe { powered } => *powered = rear > 0,
+                Node::Torch { lit } => *late = *lit == (rear > 0),
+                Node::Comparator {
+                    mode,
+                    entity_power,
+                    signal,
+                    next_signal,
+                } => {
+                    let rear = rear.max(entity_power.unwrap_or(0));
+                    *next_signal = match mode {
+                        ComparatorMode::Compare if side <= rear => rear,
+                        ComparatorMode::Compare => 0,
+                        ComparatorMode::Subtract => rear.saturating_sub(side),
+                    };
+                    *late = signal != next_signal;
+                }
+                _ => {}
+            }
+        }
+
+        // Repeaters lock each other based on their locking signals, so update them until those
+        // no longer change.
+        loop {
+            let mut changed = false;
+            for (i, late) in late.iter_mut().enumerate() {
+                if let Node::Repeater { locking_signal, .. } = self.nodes[i] {
+                    *late |= self.update_repeater(i);
+                    changed |= !matches!(
+                        self.nodes[i],
+                        Node::Repeater { locking_signal: l, .. } if l == locking_signal
+                    );
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        // End-of-tick updates
+        for i in (0..self.nodes.len()).filter(|&i| late[i]) {
+            match &mut self.nodes[i] {
+                Node::Torch { lit } => *lit = !*lit,
+                Node::Comparator {
+                    signal,
+                    next_signal,
+                    ..
+                } => *signal = *next_signal,
+                Node::Repeater {
+                    delay,
+                    powered,
+                    locking_signal,
+                    count,
+                    ..
+                } => {
+                    *count += 1;
+                    if count == delay {
+                        *count = 0;
+                        *powered = *locking_signal;
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        self.tick_counter += 1;
+    }
+
+    fn toggle_triggers(&mut self) {
+        for &(_, i) in &self.triggers {
+            if let Node::Trigger { powered } = &mut self.nodes[i] {
+                *powered = !*powered;
+            }
+        }
+    }
+
+    fn is_powered(&self, i: usize) -> bool {
+        matches!(self.nodes[i], Node::Probe { powered: true })
+    }
+
+    /// Computes the signal strength that every node outputs, starting from the components and
+    /// spreading it through the wires.
+    fn propagate(&mut self) {
+        for (power, node) in self.power.iter_mut().zip(&self.nodes) {
+            *power = match *node {
+                Node::Wire | Node::Probe { .. } => 0,
+                Node::Trigger { powered } => powered as u8 * 15,
+                Node::RedstoneBlock => 15,
+                Node::Torch { lit } => lit as u8 * 15,
+                Node::Repeater { powered, .. } => powered as u8 * 15,
+                Node::Comparator { signal, .. } => signal,
+            };
+        }
+
+        let mut queue: VecDeque<usize> = (0..self.nodes.len())
+            .filter(|&i| matches!(self.nodes[i], Node::Wire))
+            .collect();
+        while let Some(i) = queue.pop_front() {
+            let power = self.rear_power(i);
+            if power > self.power[i] {
+                self.power[i] = power;
+                queue.extend(&self.outgoing_wires[i]);
+            }
+        }
+    }
+
+    fn rear_power(&self, i: usize) -> u8 {
+        self.incoming_rear[i]
+            .iter()
+            .map(|&(s, w)| self.power[s].saturating_sub(w))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn side_power(&self, i: usize) -> u8 {
+        self.incoming_side[i]
+            .iter()
+            .map(|&(s, w)| self.power[s].saturating_sub(w))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether a repeater or comparator locks the repeaters it points into from the side.
+    fn will_lock(&self, i: usize) -> bool {
+        match self.nodes[i] {
+            Node::Repeater { locking_signal, .. } => locking_signal,
+            Node::Comparator { signal, .. } => signal > 0,
+            _ => false,
+        }
+    }
+
+    /// Updates the repeater at `i`, returns whether it requires an end-of-tick update.
+    fn update_repeater(&mut self, i: usize) -> bool {
+        if self.side_power(i) > 0 {
+            return false;
+        }
+
+        let s_new = self.rear_power(i) > 0;
+        let locked_next_tick = self.incoming_side[i]
+            .iter()
+            .any(|&(s, _)| self.will_lock(s));
+
+        let Node::Repeater {
+            delay,
+            powered,
+            next_powered,
+            locking_signal,
+            count,
+        } = &mut self.nodes[i]
+        else {
+            unreachable!()
+        };
+
+        if s_new && !*next_powered {
+            // Signal changed upwards: update next signal and reset count.
+            *next_powered = true;
+            *count = 0;
+        } else if !s_new && *next_powered && *count == 0 {
+            // Signal changed downward, and is not propagating already: update next signal.
+            *next_powered = false;
+        }
+
+        *locking_signal = if locked_next_tick {
+            *powered
+        } else if *count + 1 == *delay {
+            *next_powered
+        } else {
+            *powered
+        };
+
+        *powered != *next_powered
+    }
+}
+
+/// First tick at which a probe of a `World` disagrees with a `ReferenceWorld`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disagreement {
+    pub tick: usize,
+    pub probe: String,
+    pub world: bool,
+    pub reference: bool,
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "probe {} is {} in the world but {} in the reference at tick {}",
+            self.probe, self.world, self.reference, self.tick
+        )
+    }
+}
+
+/// Steps a `World` and a `ReferenceWorld` side by side, setting the given triggers before every
+/// tick, and returns the first tick at which any of their probes disagree.
+pub fn differential(
+    world: &mut World,
+    reference: &mut ReferenceWorld,
+    inputs: &[Vec<(String, bool)>],
+) -> Option<Disagreement> {
+    let compare = |world: &World, reference: &ReferenceWorld| {
+        let mut probes = world.get_probes().into_iter().collect::<Vec<_>>();
+        probes.sort();
+        probes.into_iter().find_map(|(probe, v)| {
+            let r = reference.get_probe(probe).unwrap_or(false);
+            (v != r).then(|| Disagreement {
+                tick: world.tick_counter(),
+                probe: probe.to_string(),
+                world: v,
+                reference: r,
+            })
+        })
+    };
+
+    if let Some(d) = compare(world, reference) {
+        return Some(d);
+    }
+    for tick in inputs {
+        for (name, powered) in tick {
+            world.set_trigger(name, *powered);
+            reference.set_trigger(name, *powered);
+        }
+        world.step();
+        reference.step();
+        if let Some(d) = compare(world, reference) {
+            return Some(d);
+        }
+    }
+    None
+}
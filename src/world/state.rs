@@ -0,0 +1,163 @@
+use crate::blocks::{Block, BlockState, RuntimeState};
+use crate::world::graph::GNode;
+use crate::world::{UpdatableList, World};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences, IntoNodeReferences};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::{error, fmt, io};
+
+/// Version of the state file format, bumped whenever `StateFile` changes.
+const STATE_VERSION: u32 = 2;
+
+/// On-disk representation of the runtime state of a `World`.
+#[derive(Serialize, Deserialize)]
+struct StateFile {
+    version: u32,
+
+    /// Fingerprint of the pruned graph the state was taken from.
+    fingerprint: u64,
+
+    tick_counter: usize,
+
+    /// Runtime state of every node, in the order of `World::blocks`.
+    nodes: Vec<BlockState>,
+
+    updatable: Vec<u32>,
+    tick_updatable_up: Vec<u32>,
+    tick_updatable_down: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub enum StateError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    /// The state file was written by an incompatible version.
+    Version(u32),
+    /// The state file was taken from a world built from a different schematic.
+    Mismatch,
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Io(e) => write!(f, "could not access state file: {e}"),
+            StateError::Format(e) => write!(f, "malformed state file: {e}"),
+            StateError::Version(v) => write!(
+                f,
+                "state file has version {v}, expected version {STATE_VERSION}"
+            ),
+            StateError::Mismatch => write!(f, "state file belongs to a different world"),
+        }
+    }
+}
+
+impl error::Error for StateError {}
+
+impl From<serde_json::Error> for StateError {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            StateError::Io(e.into())
+        } else {
+            StateError::Format(e)
+        }
+    }
+}
+
+impl World {
+    /// Writes the runtime state of the world, so it can be resumed later using `load_state`.
+    pub fn save_state(&self, w: impl Write) -> Result<(), StateError> {
+        let positions: HashMap<*const GNode<Block, u8>, u32> = self
+            .blocks
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (n as *const _, i as u32))
+            .collect();
+        let to_indices =
+            |list: &UpdatableList| list.iter().map(|&n| positions[&(n as *const _)]).collect();
+
+        let state = StateFile {
+            version: STATE_VERSION,
+            fingerprint: self.fingerprint(),
+            tick_counter: self.tick_counter,
+            nodes: self.blocks.nodes.iter().map(|n| n.weight.state()).collect(),
+            updatable: to_indices(&self.updatable),
+            tick_updatable_up: to_indices(&self.tick_updatable.up),
+            tick_updatable_down: to_indices(&self.tick_updatable.down),
+        };
+
+        serde_json::to_writer(w, &state)?;
+        Ok(())
+    }
+
+    /// Restores a runtime state written by `save_state`.
+    /// Refuses states that were taken from a world built from a different schematic.
+    pub fn load_state(&mut self, r: impl Read) -> Result<(), StateError> {
+        let state: StateFile = serde_json::from_reader(r)?;
+
+        if state.version != STATE_VERSION {
+            return Err(StateError::Version(state.version));
+        }
+        if state.fingerprint != self.fingerprint() || state.nodes.len() != self.blocks.nodes.len() {
+            return Err(StateError::Mismatch);
+        }
+
+        let nodes = &self.blocks.nodes;
+        let from_indices = |list: &[u32]| -> Result<UpdatableList, StateError> {
+            list.iter()
+                .map(|&i| nodes.get(i as usize).copied().ok_or(StateError::Mismatch))
+                .collect()
+        };
+        let updatable = from_indices(&state.updatable)?;
+        let up = from_indices(&state.tick_updatable_up)?;
+        let down = from_indices(&state.tick_updatable_down)?;
+
+        // Restore the nodes that were already loaded if any node refuses its state, so a failed
+        // load leaves the world intact.
+        let previous: Vec<BlockState> = nodes.iter().map(|n| n.weight.state()).collect();
+        for (i, (n, s)) in nodes.iter().zip(&state.nodes).enumerate() {
+            if !n.weight.set_state(s) {
+                for (n, p) in nodes.iter().zip(&previous).take(i) {
+                    n.weight.set_state(p);
+                }
+                return Err(StateError::Mismatch);
+            }
+        }
+
+        self.updatable = updatable;
+        self.tick_updatable.up = up;
+        self.tick_updatable.down = down;
+        self.tick_counter = state.tick_counter;
+        self.probes_updated = true;
+
+        Ok(())
+    }
+
+    /// Returns a hash of the pruned graph, which identifies the schematic the world was built from.
+    fn fingerprint(&self) -> u64 {
+        // FNV-1a, as the hashers of the standard library are not stable between releases.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut write = |s: String| {
+            for b in s.bytes() {
+                hash ^= b as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+
+        for (idx, cblock) in self.cblocks.node_references() {
+            write(format!("{}:{:?};", idx.index(), cblock));
+        }
+        for edge in self.cblocks.edge_references() {
+            write(format!(
+                "{}->{}:{:?};",
+                edge.source().index(),
+                edge.target().index(),
+                edge.weight()
+            ));
+        }
+
+        hash
+    }
+}
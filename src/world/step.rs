@@ -1,15 +1,27 @@
-use crate::blocks::{Block, Updatable};
-use crate::world::World;
+use crate::blocks::repeater::IdleUpdate;
+use crate::blocks::{Block, OutputPower, Updatable};
+use crate::world::graph::GNode;
+use crate::world::{Oscillating, UpdatableList, World};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem;
+
+/// Idle updates of the repeaters that are updated during idle ticks.
+type IdleUpdates = HashMap<*const GNode<Block, u8>, (&'static GNode<Block, u8>, IdleUpdate)>;
+
+/// Counting repeaters, keyed by the tick in which they change their output.
+type FireTicks = BTreeMap<usize, Vec<&'static GNode<Block, u8>>>;
 
 impl World {
     pub fn step(&mut self) {
         // Tick updates
         while let Some(idx) = self.tick_updatable.up.pop() {
+            self.probes_updated |= matches!(idx.weight, Block::Redstone(_));
             if idx.weight.update(idx, &mut self.tick_updatable.down, true) {
                 self.updatable.push(idx);
             }
         }
         while let Some(idx) = self.tick_updatable.down.pop() {
+            self.probes_updated |= matches!(idx.weight, Block::Redstone(_));
             if idx.weight.update(idx, &mut self.tick_updatable.down, false) {
                 self.updatable.push(idx);
             }
@@ -53,7 +65,7 @@ impl World {
 
     pub fn step_with_trigger(&mut self) {
         // put redstone power on triggers
-        for &t in &self.triggers {
+        for &(_, t) in &self.triggers {
             let Block::Redstone(r) = &t.weight else {
                 unreachable!()
             };
@@ -65,7 +77,7 @@ impl World {
         self.step();
 
         // take redstone power off triggers
-        for &t in &self.triggers {
+        for &(_, t) in &self.triggers {
             let Block::Redstone(r) = &t.weight else {
                 unreachable!()
             };
@@ -74,4 +86,211 @@ impl World {
             self.tick_updatable.down.extend(t.outgoing_neighbours());
         }
     }
+
+    /// Powers or unpowers every trigger with the given name until it is set again.
+    /// Returns `false` if there is no such trigger.
+    pub fn set_trigger(&mut self, name: &str, powered: bool) -> bool {
+        let mut found = false;
+        for &(_, t) in self.triggers.iter().filter(|(s, _)| s == name) {
+            found = true;
+            let Block::Redstone(r) = &t.weight else {
+                unreachable!()
+            };
+            if (r.output_power() > 0) == powered {
+                continue;
+            }
+            r.toggle_signal();
+
+            if powered {
+                self.tick_updatable.up.extend(t.outgoing_neighbours());
+            } else {
+                self.tick_updatable.down.extend(t.outgoing_neighbours());
+            }
+        }
+
+        found
+    }
+
+    /// Steps `n` ticks. Ticks in which repeaters only count down are skipped over at once,
+    /// so the time taken is proportional to the number of output changes rather than to `n`.
+    pub fn step_n(&mut self, n: usize) {
+        self.step_n_inner(n, |_| {});
+    }
+
+    /// Steps `n` ticks like `step_n`, calling `on_probe_change` with the tick counter, name and
+    /// new value of every probe that changes.
+    pub fn step_n_with(&mut self, n: usize, mut on_probe_change: impl FnMut(usize, &str, bool)) {
+        self.step_n_inner(n, |world| {
+            let tick = world.tick_counter;
+            for (probe, v) in world.probe_changes() {
+                on_probe_change(tick, probe, v);
+            }
+        });
+    }
+
+    /// Steps `n` ticks, calling `after_step` after every tick that was not skipped over.
+    fn step_n_inner(&mut self, n: usize, mut after_step: impl FnMut(&mut World)) {
+        let mut remaining = n;
+        while remaining > 0 {
+            if self.is_stable() {
+                self.tick_counter += remaining;
+                return;
+            }
+
+            let idle = self.idle_ticks().map(|(updates, fire_ticks)| {
+                let ticks = fire_ticks
+                    .first_key_value()
+                    .map_or(usize::MAX, |(&tick, _)| tick - self.tick_counter);
+                (ticks, updates, fire_ticks)
+            });
+            match idle {
+                Some((ticks, updates, fire_ticks)) if ticks > 0 => {
+                    let ticks = ticks.min(remaining);
+                    self.skip_ticks(ticks, &updates, &fire_ticks);
+                    remaining -= ticks;
+                }
+                _ => {
+                    self.step();
+                    after_step(self);
+                    remaining -= 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the updates that happen during the upcoming ticks in which nothing but repeater
+    /// counts change, together with the ticks in which the counting repeaters fire.
+    fn idle_ticks(&self) -> Option<(IdleUpdates, FireTicks)> {
+        if !self.updatable.is_empty() || !self.tick_updatable.up.is_empty() {
+            return None;
+        }
+
+        let mut updates = IdleUpdates::new();
+        let mut fire_ticks = FireTicks::new();
+        let mut todo = self.tick_updatable.down.clone();
+        while let Some(idx) = todo.pop() {
+            if updates.contains_key(&(idx as *const _)) {
+                continue;
+            }
+            let Block::Repeater(r) = &idx.weight else {
+                return None;
+            };
+
+            let update = r.idle_update(idx)?;
+            if let Some(left) = update.ticks_left {
+                fire_ticks
+                    .entry(self.tick_counter + left as usize)
+                    .or_default()
+                    .push(idx);
+            }
+            if update.pushes_neighbours {
+                todo.extend(repeater_neighbours(idx));
+            }
+            updates.insert(idx, (idx, update));
+        }
+
+        Some((updates, fire_ticks))
+    }
+
+    /// Skips `ticks` idle ticks, leaving the world exactly as `step` would have.
+    fn skip_ticks(&mut self, ticks: usize, updates: &IdleUpdates, fire_ticks: &FireTicks) {
+        // The queue after an idle tick only depends on the queue before it, so it repeats after
+        // a few ticks. Replay it until it does and pick the queue `ticks` ticks in.
+        let mut queues = vec![mem::take(&mut self.tick_updatable.down)];
+        let down = loop {
+            if queues.len() > ticks {
+                break queues.swap_remove(ticks);
+            }
+            let next = idle_queue(queues.last().unwrap(), updates);
+            if let Some(start) = queues.iter().position(|q| same_queue(q, &next)) {
+                let period = queues.len() - start;
+                break queues.swap_remove(start + (ticks - start) % period);
+            }
+            queues.push(next);
+        };
+        self.tick_updatable.down = down;
+
+        let last_tick = self.tick_counter + ticks - 1;
+        for idx in fire_ticks.values().flatten() {
+            if let Block::Repeater(r) = &idx.weight {
+                r.skip(ticks as u8, last_tick);
+            }
+        }
+
+        self.tick_counter += ticks;
+    }
+
+    /// Returns whether there are no pending updates, so stepping won't change the world anymore.
+    pub fn is_stable(&self) -> bool {
+        self.updatable.is_empty()
+            && self.tick_updatable.up.is_empty()
+            && self.tick_updatable.down.is_empty()
+    }
+
+    /// Steps until the world is stable, returning the number of ticks that elapsed.
+    /// Fails if the world is still changing after `max_ticks` ticks.
+    pub fn run_until_stable(&mut self, max_ticks: usize) -> Result<usize, Oscillating> {
+        for ticks in 0..max_ticks {
+            if self.is_stable() {
+                return Ok(ticks);
+            }
+            self.step();
+        }
+
+        if self.is_stable() {
+            Ok(max_ticks)
+        } else {
+            Err(Oscillating { ticks: max_ticks })
+        }
+    }
+
+    /// Steps until `predicate` holds, returning the number of ticks that elapsed.
+    /// Returns `None` if it did not hold within `max_ticks` ticks.
+    pub fn run_until(
+        &mut self,
+        mut predicate: impl FnMut(&World) -> bool,
+        max_ticks: usize,
+    ) -> Option<usize> {
+        for ticks in 0..max_ticks {
+            if predicate(self) {
+                return Some(ticks);
+            }
+            self.step();
+        }
+
+        predicate(self).then_some(max_ticks)
+    }
+}
+
+fn repeater_neighbours(
+    idx: &'static GNode<Block, u8>,
+) -> impl Iterator<Item = &'static GNode<Block, u8>> {
+    idx.outgoing_neighbours()
+        .filter(|b| matches!(b.weight, Block::Repeater(_)))
+}
+
+/// Returns the queue of repeaters to update after an idle tick that starts with `queue`.
+fn idle_queue(queue: &UpdatableList, updates: &IdleUpdates) -> UpdatableList {
+    let mut todo = queue.clone();
+    let mut updatable = UpdatableList::new();
+    while let Some(idx) = todo.pop() {
+        let (_, update) = updates[&(idx as *const _)];
+        if update.pushes_neighbours {
+            todo.extend(repeater_neighbours(idx));
+        }
+        if update.ticks_left.is_some() {
+            updatable.push(idx);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    updatable
+        .into_iter()
+        .filter(|&idx| seen.insert(idx as *const _))
+        .collect()
+}
+
+/// Returns whether two queues hold the same blocks in the same order.
+fn same_queue(a: &UpdatableList, b: &UpdatableList) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| std::ptr::eq(a, b))
 }
@@ -0,0 +1,142 @@
+use crate::blocks::CBlock;
+use crate::world::builder::WorldBuilder;
+use crate::world::provenance::Position;
+use crate::world::World;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::{error, fmt};
+
+/// Extension of text schematic files.
+pub const EXTENSION: &str = "tschem";
+
+/// Malformed text schematic, with the number of the offending line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSchematicError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for TextSchematicError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl error::Error for TextSchematicError {}
+
+/// Parses a text schematic into a builder, which places every block of its grids.
+///
+/// A text schematic is a list of directives, one per line, followed by a grid for every Y level:
+///
+/// ```text
+/// # A torch that inverts its input.
+/// legend G minecraft:gold_block
+/// legend - minecraft:redstone_wire[east=side,north=none,power=0,south=none,west=side]
+/// legend S minecraft:stone
+/// legend t minecraft:redstone_wall_torch[facing=east,lit=true]
+/// legend D minecraft:diamond_block
+/// name 0 0 0 in
+/// name 5 0 0 out
+///
+/// layer 0
+/// G-St-D
+/// ```
+///
+/// Every grid row is a Z coordinate and every character an X coordinate, starting at 0. `.` and
+/// spaces are air, and a grid ends at an empty line. `name x y z name` names a trigger or probe,
+/// and `signal x y z strength` sets the initial output of a comparator. Lines outside of grids
+/// that start with `#` are comments.
+pub fn parse(s: &str) -> Result<WorldBuilder, TextSchematicError> {
+    let mut builder = WorldBuilder::new();
+    let mut legend: HashMap<char, &str> = HashMap::new();
+
+    // Y level and Z coordinate of the next row while inside a grid.
+    let mut grid: Option<(isize, isize)> = None;
+
+    for (i, line) in s.lines().enumerate() {
+        let error = |message: &str| TextSchematicError {
+            line: i + 1,
+            message: message.to_string(),
+        };
+
+        if let Some((y, z)) = grid {
+            if line.trim().is_empty() {
+                grid = None;
+                continue;
+            }
+            for (x, c) in line.chars().enumerate() {
+                if c == '.' || c == ' ' {
+                    continue;
+                }
+                let id = legend
+                    .get(&c)
+                    .ok_or_else(|| error(&format!("character {c:?} is not in the legend")))?;
+                builder.set((x as isize, y, z), id);
+            }
+            grid = Some((y, z + 1));
+            continue;
+        }
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (directive, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let args = args.trim();
+        match directive {
+            "legend" => {
+                let (c, id) = args
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| error("expected a character and a block id"))?;
+                let mut chars = c.chars();
+                let (Some(c), None) = (chars.next(), chars.next()) else {
+                    return Err(error("legend keys must be a single character"));
+                };
+                if c == '.' {
+                    return Err(error("'.' is always air"));
+                }
+                let id = id.trim();
+                CBlock::check_id(id).map_err(|e| error(&e))?;
+                legend.insert(c, id);
+            }
+            "name" => {
+                let (pos, name) = position(args).ok_or_else(|| error("expected x y z name"))?;
+                if name.is_empty() {
+                    return Err(error("expected x y z name"));
+                }
+                builder.name(pos, name);
+            }
+            "signal" => {
+                let (pos, signal) = position(args).ok_or_else(|| error("expected x y z signal"))?;
+                let signal = signal
+                    .parse()
+                    .ok()
+                    .filter(|&s: &u8| s <= 15)
+                    .ok_or_else(|| error("signal strengths range from 0 to 15"))?;
+                builder.comparator_signal(pos, signal);
+            }
+            "layer" => {
+                let y = args.parse().map_err(|_| error("expected a Y level"))?;
+                grid = Some((y, 0));
+            }
+            _ => return Err(error(&format!("unknown directive {directive}"))),
+        }
+    }
+
+    Ok(builder)
+}
+
+/// Splits the coordinates off the start of the arguments.
+fn position(args: &str) -> Option<(Position, &str)> {
+    let mut parts = args.splitn(4, char::is_whitespace);
+    let mut coordinate = || parts.next()?.parse::<isize>().ok();
+    let pos = (coordinate()?, coordinate()?, coordinate()?);
+    Some((pos, parts.next().unwrap_or("").trim()))
+}
+
+impl World {
+    /// Builds the world of a text schematic, like `World::from` does for a schematic file.
+    pub fn from_text(s: &str) -> Result<World, TextSchematicError> {
+        Ok(parse(s)?.build())
+    }
+}
@@ -0,0 +1,288 @@
+use crate::blocks::CBlock;
+use crate::world::provenance::{Position, Provenance};
+use crate::world::{CBlockGraph, World};
+use itertools::Itertools;
+use petgraph::algo::tarjan_scc;
+use petgraph::prelude::*;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+
+/// Delays of the paths from a trigger to a probe, in ticks between setting the trigger and the
+/// step in which the probe can change.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PathDelay {
+    pub trigger: String,
+    pub probe: String,
+
+    /// Delay of the fastest path.
+    pub min: usize,
+
+    /// Delay of the slowest path, `None` if a path passes through a feedback loop.
+    pub max: Option<usize>,
+
+    /// Positions of the blocks on the slowest path from the trigger to the probe, empty if the
+    /// delay is unbounded.
+    pub critical_path: Vec<Position>,
+}
+
+/// Delays between every trigger and every probe it can reach, sorted by trigger and probe.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TimingReport {
+    pub paths: Vec<PathDelay>,
+}
+
+impl TimingReport {
+    /// Returns the path with the largest bounded delay.
+    pub fn critical(&self) -> Option<&PathDelay> {
+        self.paths
+            .iter()
+            .filter(|p| p.max.is_some())
+            .max_by_key(|p| p.max)
+    }
+}
+
+impl fmt::Display for TimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<20} {:<20} {:>10} {:>10}",
+            "trigger", "probe", "min", "max"
+        )?;
+        for p in &self.paths {
+            let max = p.max.map_or("unbounded".to_string(), |m| m.to_string());
+            write!(
+                f,
+                "\n{:<20} {:<20} {:>10} {:>10}",
+                p.trigger, p.probe, p.min, max
+            )?;
+        }
+        if let Some(p) = self.critical() {
+            write!(
+                f,
+                "\n\ncritical path {} -> {}: {}",
+                p.trigger,
+                p.probe,
+                p.critical_path.iter().map(|p| format!("{p:?}")).join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Ticks from a change of the inputs of the node until its output changes. Probes take a tick to
+/// read their input, like the other blocks.
+fn delay(cblock: &CBlock) -> usize {
+    match cblock {
+        CBlock::Repeater(r) => r.delay() as usize,
+        CBlock::DelayLine(d) => d.delay(),
+        CBlock::Buffer(b) => b.delay() as usize,
+        CBlock::SRepeater(_)
+        | CBlock::Torch(_)
+        | CBlock::Comparator(_)
+        | CBlock::BinaryComparator(_)
+        | CBlock::Probe(_) => 1,
+        CBlock::Trigger(_) | CBlock::RedstoneBlock(_) => 0,
+        CBlock::Redstone(_) | CBlock::SolidWeak(_) | CBlock::SolidStrong(_) => {
+            unreachable!("Dust and solid blocks should have been pruned.")
+        }
+    }
+}
+
+/// Returns the edges that can carry a signal out of the node. Side edges into repeaters count as
+/// well, since unlocking a repeater can change its output.
+fn outgoing(
+    cblocks: &CBlockGraph,
+    idx: NodeIndex,
+) -> impl Iterator<Item = (EdgeIndex, NodeIndex)> + '_ {
+    cblocks
+        .edges_directed(idx, Outgoing)
+        .filter(|e| e.weight().strength_loss() < 15)
+        .map(|e| (e.id(), e.target()))
+}
+
+/// Computes the delays between every trigger and every probe it can reach in the pruned graph.
+pub fn analyze(cblocks: &CBlockGraph, provenance: &Provenance) -> TimingReport {
+    let cyclic: HashSet<NodeIndex> = tarjan_scc(cblocks)
+        .into_iter()
+        .filter(|c| c.len() > 1 || cblocks.contains_edge(c[0], c[0]))
+        .flatten()
+        .collect();
+    let probes = cblocks
+        .node_indices()
+        .filter_map(|idx| match &cblocks[idx] {
+            CBlock::Probe(p) => Some((p.name.clone(), idx)),
+            _ => None,
+        })
+        .sorted()
+        .collect_vec();
+
+    // Triggers with the same name act as one.
+    let triggers = cblocks
+        .node_indices()
+        .filter_map(|idx| match &cblocks[idx] {
+            CBlock::Trigger(t) => Some((t.name.clone(), idx)),
+            _ => None,
+        })
+        .into_group_map();
+
+    let mut paths = vec![];
+    for (trigger, nodes) in triggers.into_iter().sorted() {
+        let shortest = nodes
+            .iter()
+            .map(|&idx| shortest(cblocks, idx))
+            .collect_vec();
+        for (probe, probe_idx) in &probes {
+            let mut delay: Option<PathDelay> = None;
+            for (&idx, min) in nodes.iter().zip(&shortest) {
+                let Some(&min) = min.get(probe_idx) else {
+                    continue;
+                };
+                let (max, critical_path) = match longest(cblocks, &cyclic, idx, *probe_idx) {
+                    Some((max, path)) => (Some(max), positions(cblocks, provenance, idx, &path)),
+                    None => (None, vec![]),
+                };
+                let other = PathDelay {
+                    trigger: trigger.clone(),
+                    probe: probe.clone(),
+                    min,
+                    max,
+                    critical_path,
+                };
+                delay = Some(match delay {
+                    Some(delay) => merge(delay, other),
+                    None => other,
+                });
+            }
+            paths.extend(delay);
+        }
+    }
+
+    TimingReport { paths }
+}
+
+/// Combines the delays from two triggers with the same name to the same probe.
+fn merge(a: PathDelay, b: PathDelay) -> PathDelay {
+    let (max, critical_path) = match (a.max, b.max) {
+        (Some(x), Some(y)) if x >= y => (a.max, a.critical_path),
+        (Some(_), Some(_)) => (b.max, b.critical_path),
+        _ => (None, vec![]),
+    };
+    PathDelay {
+        min: a.min.min(b.min),
+        max,
+        critical_path,
+        ..a
+    }
+}
+
+/// Returns the delay of the fastest path from the trigger to every node it reaches.
+fn shortest(cblocks: &CBlockGraph, trigger: NodeIndex) -> HashMap<NodeIndex, usize> {
+    let mut dist = HashMap::from([(trigger, 0)]);
+    let mut queue = BinaryHeap::from([Reverse((0, trigger))]);
+    while let Some(Reverse((d, idx))) = queue.pop() {
+        if dist[&idx] < d {
+            continue;
+        }
+        for (_, next) in outgoing(cblocks, idx) {
+            let nd = d + delay(&cblocks[next]);
+            if dist.get(&next).is_none_or(|&old| nd < old) {
+                dist.insert(next, nd);
+                queue.push(Reverse((nd, next)));
+            }
+        }
+    }
+    dist
+}
+
+/// Returns the delay of the slowest path from the trigger to the probe, together with the edges on
+/// it. Returns `None` if a path passes through a feedback loop.
+fn longest(
+    cblocks: &CBlockGraph,
+    cyclic: &HashSet<NodeIndex>,
+    trigger: NodeIndex,
+    probe: NodeIndex,
+) -> Option<(usize, Vec<EdgeIndex>)> {
+    // Nodes on a path from the trigger to the probe.
+    let mut forward = HashSet::from([trigger]);
+    let mut todo = vec![trigger];
+    while let Some(idx) = todo.pop() {
+        for (_, next) in outgoing(cblocks, idx) {
+            if forward.insert(next) {
+                todo.push(next);
+            }
+        }
+    }
+    let mut relevant = HashSet::from([probe]);
+    let mut todo = vec![probe];
+    while let Some(idx) = todo.pop() {
+        for e in cblocks.edges_directed(idx, Incoming) {
+            if e.weight().strength_loss() < 15
+                && forward.contains(&e.source())
+                && relevant.insert(e.source())
+            {
+                todo.push(e.source());
+            }
+        }
+    }
+    if relevant.iter().any(|idx| cyclic.contains(idx)) {
+        return None;
+    }
+
+    // The relevant nodes form a DAG, so the longest paths follow from a topological order.
+    let mut indegree: HashMap<NodeIndex, usize> = HashMap::new();
+    for &idx in &relevant {
+        for (_, next) in outgoing(cblocks, idx).filter(|(_, n)| relevant.contains(n)) {
+            *indegree.entry(next).or_default() += 1;
+        }
+    }
+    let mut dist: HashMap<NodeIndex, (usize, Option<EdgeIndex>)> =
+        HashMap::from([(trigger, (0, None))]);
+    let mut ready = vec![trigger];
+    while let Some(idx) = ready.pop() {
+        let d = dist[&idx].0;
+        for (e, next) in outgoing(cblocks, idx).filter(|(_, n)| relevant.contains(n)) {
+            let nd = d + delay(&cblocks[next]);
+            if dist.get(&next).is_none_or(|&(old, _)| nd > old) {
+                dist.insert(next, (nd, Some(e)));
+            }
+            let left = indegree.get_mut(&next).unwrap();
+            *left -= 1;
+            if *left == 0 {
+                ready.push(next);
+            }
+        }
+    }
+
+    let mut path = vec![];
+    let mut idx = probe;
+    while let (_, Some(e)) = dist[&idx] {
+        path.push(e);
+        idx = cblocks.edge_endpoints(e).unwrap().0;
+    }
+    path.reverse();
+    Some((dist[&probe].0, path))
+}
+
+/// Returns the positions of the blocks on the path that starts at the trigger.
+fn positions(
+    cblocks: &CBlockGraph,
+    provenance: &Provenance,
+    trigger: NodeIndex,
+    path: &[EdgeIndex],
+) -> Vec<Position> {
+    let mut positions = provenance.node(trigger).to_vec();
+    for &e in path {
+        positions.extend(provenance.edge(e));
+        positions.extend(provenance.node(cblocks.edge_endpoints(e).unwrap().1));
+    }
+    positions
+}
+
+impl World {
+    /// Computes the delays between every trigger and every probe it can reach.
+    pub fn timing(&self) -> TimingReport {
+        analyze(&self.cblocks, &self.provenance)
+    }
+}
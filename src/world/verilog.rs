@@ -0,0 +1,184 @@
+use crate::blocks::comparator::ComparatorMode;
+use crate::blocks::BlockState;
+use crate::world::compiled::{CompileError, CompiledGraph, NodeKind};
+use crate::world::World;
+use itertools::Itertools;
+use std::fmt::Write;
+
+/// Behavioural models of the blocks instantiated by `netlist`, which have to be read together with
+/// the netlist.
+pub const PRIMITIVES: &str = include_str!("../../resources/primitives.v");
+
+/// Writes a formatted line to the netlist.
+macro_rules! emit {
+    ($out:expr) => {
+        writeln!($out).unwrap()
+    };
+    ($out:expr, $($arg:tt)*) => {
+        writeln!($out, $($arg)*).unwrap()
+    };
+}
+
+/// Generates a structural Verilog netlist of the pruned graph of `world` from its current state.
+///
+/// The netlist is a single module with a clock `clk`, of which every rising edge is a tick, an
+/// input for every trigger and an output for every probe. Every other node is an instance of one
+/// of the `PRIMITIVES`, driving a 4-bit wire with its output power. Fails if the world cannot be
+/// compiled.
+pub fn netlist(world: &World, module: &str) -> Result<String, CompileError> {
+    let graph = CompiledGraph::new(world)?;
+    let mut out = String::new();
+
+    let triggers = graph.triggers.iter().map(|(s, _)| s.as_str()).unique();
+    let probes = graph.probes.iter().map(|(s, _)| s.as_str()).unique();
+    let ports = ["input wire clk".to_string()]
+        .into_iter()
+        .chain(triggers.map(|t| format!("input wire {}", identifier(t))))
+        .chain(probes.map(|p| format!("output wire {}", identifier(p))))
+        .join(",\n    ");
+
+    emit!(out, "// Generated by redstone-simulator, do not edit.");
+    emit!(out);
+    emit!(out, "module {} (\n    {ports}\n);", identifier(module));
+    emit!(
+        out,
+        "    // Signal strength through an edge with strength loss `w`."
+    );
+    emit!(
+        out,
+        "    function [3:0] loss(input [3:0] s, input [3:0] w);"
+    );
+    emit!(out, "        loss = s > w ? s - w : 4'd0;");
+    emit!(out, "    endfunction");
+    emit!(out);
+    emit!(out, "    function [3:0] max(input [3:0] a, input [3:0] b);");
+    emit!(out, "        max = a > b ? a : b;");
+    emit!(out, "    endfunction");
+    emit!(out);
+
+    for (n, kind) in graph.kinds.iter().enumerate() {
+        emit!(out, "    wire [3:0] n{n};");
+        if let NodeKind::Repeater { .. } = kind {
+            // Whether the repeater is powered after the tick.
+            emit!(out, "    wire l{n};");
+        }
+    }
+
+    // Returns an expression for whether any of the given edges carries a signal.
+    let any_input = |edges: &mut dyn Iterator<Item = (u32, u8)>| {
+        let inputs = edges.map(|(m, w)| format!("n{m} > 4'd{w}")).join(" || ");
+        if inputs.is_empty() {
+            "1'b0".to_string()
+        } else {
+            inputs
+        }
+    };
+    // Returns an expression for the strongest signal through the given edges.
+    let max_input = |edges: &mut dyn Iterator<Item = (u32, u8)>, start: u8| {
+        edges.fold(format!("4'd{start}"), |acc, (m, w)| {
+            format!("max({acc}, loss(n{m}, 4'd{w}))")
+        })
+    };
+
+    for (n, (kind, state)) in graph.kinds.iter().zip(&graph.states).enumerate() {
+        emit!(out);
+        if let Some((x, y, z)) = graph.positions[n] {
+            emit!(out, "    // ({x}, {y}, {z})");
+        }
+        let i = n as u32;
+        match *kind {
+            NodeKind::Trigger => {
+                let (name, _) = graph.triggers.iter().find(|&&(_, t)| t == i).unwrap();
+                emit!(
+                    out,
+                    "    assign n{n} = {} ? 4'd15 : 4'd0;",
+                    identifier(name)
+                );
+            }
+            NodeKind::Probe => {
+                let on = any_input(&mut graph.incoming_rear.edges(i));
+                emit!(out, "    assign n{n} = {on} ? 4'd15 : 4'd0;");
+            }
+            NodeKind::SRepeater => {
+                let BlockState::SRepeater { powered, on_inputs } = *state else {
+                    unreachable!();
+                };
+                // Torches and redstone blocks are powered while none of their inputs are.
+                let invert = powered != (on_inputs > 0);
+                let input = any_input(
+                    &mut graph
+                        .incoming_rear
+                        .edges(i)
+                        .chain(graph.incoming_side.edges(i)),
+                );
+                emit!(
+                    out,
+                    "    rs_srepeater #(.INVERT({}), .INIT({})) b{n} (.clk(clk), .in({input}), .out(n{n}));",
+                    invert as u8, powered as u8
+                );
+            }
+            NodeKind::Repeater { delay } => {
+                let BlockState::Repeater { powered, .. } = *state else {
+                    unreachable!();
+                };
+                let input = any_input(&mut graph.incoming_rear.edges(i));
+                let lock = any_input(&mut graph.incoming_side.edges(i));
+                // Repeaters lock at the same time as the repeaters that lock them change.
+                let lock_next = graph
+                    .incoming_side
+                    .edges(i)
+                    .map(|(m, _)| match graph.kinds[m as usize] {
+                        NodeKind::Repeater { .. } => format!("l{m}"),
+                        _ => format!("n{m} > 4'd0"),
+                    })
+                    .join(" || ");
+                let lock_next = if lock_next.is_empty() {
+                    "1'b0".to_string()
+                } else {
+                    lock_next
+                };
+                emit!(
+                    out,
+                    "    rs_repeater #(.DELAY({delay}), .INIT({})) b{n} (.clk(clk), .in({input}), .lock({lock}), .lock_next({lock_next}), .out(n{n}), .out_next(l{n}));",
+                    powered as u8
+                );
+            }
+            NodeKind::Comparator { mode, entity_power } => {
+                let BlockState::Comparator { signal, .. } = *state else {
+                    unreachable!();
+                };
+                // The power of the entity behind the comparator is one of its rear inputs.
+                let rear = max_input(&mut graph.incoming_rear.edges(i), entity_power.unwrap_or(0));
+                let side = max_input(&mut graph.incoming_side.edges(i), 0);
+                let subtract = mode == ComparatorMode::Subtract;
+                emit!(
+                    out,
+                    "    rs_comparator #(.SUBTRACT({}), .INIT({signal})) b{n} (.clk(clk), .rear({rear}), .side({side}), .out(n{n}));",
+                    subtract as u8
+                );
+            }
+        }
+    }
+
+    emit!(out);
+    for (name, nodes) in &graph.probes.iter().group_by(|(s, _)| s) {
+        let on = nodes.map(|(_, n)| format!("n{n} > 4'd0")).join(" || ");
+        emit!(out, "    assign {} = {on};", identifier(name));
+    }
+    emit!(out, "endmodule");
+    Ok(out)
+}
+
+/// Returns the name as a Verilog identifier, which is escaped unless it is a simple identifier.
+pub fn identifier(name: &str) -> String {
+    let simple = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if simple {
+        name.to_string()
+    } else {
+        // Escaped identifiers end at whitespace, so it is replaced.
+        format!("\\{} ", name.replace(char::is_whitespace, "_"))
+    }
+}
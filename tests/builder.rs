@@ -0,0 +1,68 @@
+use redstone_simulator::world::builder::WorldBuilder;
+
+const WIRE_EW: &str = "minecraft:redstone_wire[east=side,north=none,power=0,south=none,west=side]";
+
+#[test]
+fn repeater() {
+    let mut world = WorldBuilder::new()
+        .trigger((0, 0, 0), "in")
+        .set(
+            (1, 0, 0),
+            "minecraft:repeater[delay=2,facing=west,locked=false,powered=false]",
+        )
+        .probe((2, 0, 0), "out")
+        .build();
+    assert_eq!(world.trigger_names().collect::<Vec<_>>(), ["in"]);
+    assert_eq!(world.get_probe("out"), Some(false));
+
+    world.set_trigger("in", true);
+    // Probes see their input one tick after the repeater.
+    let ticks = world.run_until(|w| w.get_probe("out") == Some(true), 10);
+    assert_eq!(ticks, Some(3));
+}
+
+#[test]
+fn keeps_coordinates() {
+    let world = WorldBuilder::new()
+        .trigger((-10, 5, 3), "in")
+        .set((-9, 5, 3), WIRE_EW)
+        .set((-8, 5, 3), WIRE_EW)
+        .probe((-7, 5, 3), "out")
+        .build();
+
+    let timing = world.timing();
+    let path = &timing.critical().unwrap().critical_path;
+    assert_eq!(path.first(), Some(&(-10, 5, 3)));
+    assert_eq!(path.last(), Some(&(-7, 5, 3)));
+}
+
+#[test]
+fn comparator_signal() {
+    let comparator = "minecraft:comparator[facing=west,mode=compare,powered=true]";
+    let build = |signal| {
+        WorldBuilder::new()
+            .set((0, 0, 0), "minecraft:redstone_block")
+            .set((1, 0, 0), comparator)
+            .comparator_signal((1, 0, 0), signal)
+            .probe((2, 0, 0), "out")
+            .build()
+    };
+
+    assert_eq!(build(15).get_probe("out"), Some(true));
+
+    // Like in a schematic, the initial state comes from the block entity.
+    assert_eq!(build(0).get_probe("out"), Some(false));
+}
+
+#[test]
+fn schematic_size() {
+    let mut builder = WorldBuilder::new();
+    builder.set((2, 0, -1), WIRE_EW).set((4, 1, 1), WIRE_EW);
+    let schematic = builder.schematic();
+    assert_eq!(
+        (schematic.width, schematic.height, schematic.length),
+        (3, 2, 3)
+    );
+    assert_eq!(schematic.offset, [2, 0, -1]);
+    assert_eq!(schematic.block_data.len(), 18);
+}
@@ -0,0 +1,108 @@
+use redstone_simulator::world::codegen::{generate, Target};
+use redstone_simulator::world::World;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CYCLES: usize = 10;
+
+fn world() -> World {
+    World::from(File::open("./schematics/cpu_fib.schem").unwrap())
+}
+
+/// Returns a new empty directory for compiling generated code.
+fn out_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("redstone-codegen-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn rustc(dir: &Path, args: &[&str]) {
+    let status = Command::new(std::env::var("RUSTC").unwrap_or("rustc".to_string()))
+        .current_dir(dir)
+        .args(["--edition", "2021"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// Runs the CPU like `tests/cpu.rs`, writing the probes after every tick.
+const MAIN: &str = r#"
+mod circuit {
+    include!("circuit.rs");
+}
+
+fn main() {
+    let mut state = circuit::State::default();
+    for tick in 0..CYCLES * 42 {
+        if tick % 42 < 2 {
+            state.step_with_trigger();
+        } else {
+            state.tick();
+        }
+        let probes: String = circuit::PROBES
+            .iter()
+            .map(|p| if state.get_probe(p).unwrap() { '1' } else { '0' })
+            .collect();
+        println!("{probes}");
+    }
+}
+"#;
+
+#[test]
+fn cpu_fib_trace() {
+    let mut world = world();
+    let dir = out_dir("trace");
+    std::fs::write(
+        dir.join("circuit.rs"),
+        generate(&world, Target::Module).unwrap(),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("main.rs"),
+        MAIN.replace("CYCLES", &CYCLES.to_string()),
+    )
+    .unwrap();
+    rustc(&dir, &["main.rs", "-o", "trace"]);
+    let output = Command::new(dir.join("trace")).output().unwrap();
+    let trace = String::from_utf8(output.stdout).unwrap();
+
+    let mut probes: Vec<&str> = world.get_probes().into_keys().collect();
+    probes.sort();
+    let probes: Vec<String> = probes.into_iter().map(String::from).collect();
+    let mut lines = trace.lines();
+    for tick in 0..CYCLES * 42 {
+        if tick % 42 < 2 {
+            world.step_with_trigger();
+        } else {
+            world.step();
+        }
+        let expected: String = probes
+            .iter()
+            .map(|p| {
+                if world.get_probe(p).unwrap() {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect();
+        assert_eq!(lines.next(), Some(expected.as_str()), "tick {tick}");
+    }
+    assert_eq!(lines.next(), None);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cdylib() {
+    let dir = out_dir("cdylib");
+    std::fs::write(
+        dir.join("circuit.rs"),
+        generate(&world(), Target::CDylib).unwrap(),
+    )
+    .unwrap();
+    rustc(&dir, &["--crate-type", "cdylib", "circuit.rs"]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
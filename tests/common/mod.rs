@@ -0,0 +1,105 @@
+#![allow(dead_code)]
+
+use redstone_simulator::world::flat::FlatWorld;
+use redstone_simulator::world::parallel::ParallelWorld;
+use redstone_simulator::world::schematic::{Metadata, SchemFormat};
+use redstone_simulator::world::World;
+use std::collections::HashMap;
+use std::fs::File;
+
+pub fn world(file: &str) -> World {
+    World::from(File::open(format!("./schematics/{file}")).unwrap())
+}
+
+/// A simulation backend that keeps the same runtime state as `World`.
+pub trait Backend {
+    fn step(&mut self);
+    fn step_with_trigger(&mut self);
+    /// Asserts that the state of every node is the same as in `world`.
+    fn assert_states(&self, world: &World, context: &str);
+    fn get_probe(&self, name: &str) -> Option<bool>;
+    fn tick_counter(&self) -> usize;
+}
+
+macro_rules! impl_backend {
+    ($t:ty) => {
+        impl Backend for $t {
+            fn step(&mut self) {
+                <$t>::step(self)
+            }
+            fn step_with_trigger(&mut self) {
+                <$t>::step_with_trigger(self)
+            }
+            fn assert_states(&self, world: &World, context: &str) {
+                assert_eq!(world.states(), <$t>::states(self), "{context}");
+            }
+            fn get_probe(&self, name: &str) -> Option<bool> {
+                <$t>::get_probe(self, name)
+            }
+            fn tick_counter(&self) -> usize {
+                <$t>::tick_counter(self)
+            }
+        }
+    };
+}
+
+impl_backend!(FlatWorld);
+impl_backend!(ParallelWorld);
+
+/// Steps a `World` and the backend created from it side by side, pulsing the triggers at the
+/// given ticks, and compares the state of every node after every tick.
+pub fn check<B: Backend>(
+    file: &str,
+    ticks: usize,
+    trigger: impl Fn(usize) -> bool,
+    backend: impl Fn(&World) -> B,
+) {
+    let mut world = world(file);
+    let mut other = backend(&world);
+
+    for tick in 0..ticks {
+        if trigger(tick) {
+            world.step_with_trigger();
+            other.step_with_trigger();
+        } else {
+            world.step();
+            other.step();
+        }
+        other.assert_states(&world, &format!("{file}: state differs at tick {tick}"));
+    }
+
+    for (probe, v) in world.get_probes() {
+        assert_eq!(other.get_probe(probe), Some(v), "{file}: probe {probe}");
+    }
+    assert_eq!(other.tick_counter(), world.tick_counter());
+}
+
+/// Builds a schematic in the y = 0 plane from rows of block ids, indexed as `rows[z][x]`.
+pub fn schematic(rows: &[&[&str]]) -> SchemFormat {
+    let mut palette: HashMap<String, i32> = HashMap::new();
+    let mut block_data = Vec::new();
+    for row in rows {
+        for id in *row {
+            let next = palette.len() as i32;
+            block_data.push(*palette.entry(id.to_string()).or_insert(next) as i8);
+        }
+    }
+
+    SchemFormat {
+        block_data,
+        block_entities: vec![],
+        data_version: 3218,
+        height: 1,
+        length: rows.len() as i16,
+        metadata: Metadata {
+            offset_x: 0,
+            offset_y: 0,
+            offset_z: 0,
+        },
+        offset: vec![0, 0, 0],
+        palette_max: palette.len() as i32,
+        palette,
+        version: 2,
+        width: rows[0].len() as i16,
+    }
+}
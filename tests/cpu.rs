@@ -45,6 +45,7 @@ const EXPECTED_OUTPUT: [(&str, &str); 40] = [
 ];
 
 #[test]
+#[allow(clippy::needless_range_loop)]
 fn cpu_test() {
     let mut world = World::from(File::open("./schematics/cpu_fib.schem").unwrap());
 
@@ -0,0 +1,97 @@
+mod common;
+
+use common::world;
+use redstone_simulator::world::compiled::CompileError;
+use redstone_simulator::world::flat::FlatWorld;
+use redstone_simulator::world::prune::PruneOptions;
+use redstone_simulator::world::World;
+use std::fs::File;
+
+/// Steps a `World` and a `FlatWorld` side by side and compares them after every tick.
+fn check(file: &str, ticks: usize, trigger: impl Fn(usize) -> bool) {
+    common::check(file, ticks, trigger, |world| FlatWorld::new(world).unwrap());
+}
+
+#[test]
+fn matches_world() {
+    for file in [
+        "adder.schem",
+        "comparator.schem",
+        "comparator_inputs.schem",
+        "comparator_strengths.schem",
+        "connections.schem",
+        "glass.schem",
+        "locking.schem",
+        "redstone_split.schem",
+        "repeater.schem",
+        "stress_comparator.schem",
+        "stress_srepeater.schem",
+        "torch.schem",
+    ] {
+        check(file, 100, |tick| tick % 17 == 0);
+    }
+}
+
+#[test]
+fn repeater_cycle() {
+    check("stress_repeater.schem", 200, |tick| tick == 0);
+}
+
+#[test]
+fn cpu_fib() {
+    check("cpu_fib.schem", 420, |tick| tick % 42 < 2);
+}
+
+#[test]
+fn from_running_world() {
+    // Queued updates carry over in their original order.
+    let mut world = world("cpu_fib.schem");
+    world.step_with_trigger();
+    world.step();
+    let mut flat = FlatWorld::new(&world).unwrap();
+    for _ in 0..100 {
+        world.step();
+        flat.step();
+    }
+    assert_eq!(world.states(), flat.states());
+}
+
+#[test]
+fn set_trigger() {
+    let mut world = world("repeater.schem");
+    let mut flat = FlatWorld::new(&world).unwrap();
+    assert!(flat.set_trigger("0,1,0", true));
+    assert!(world.set_trigger("0,1,0", true));
+    assert!(!flat.set_trigger("missing", true));
+    for _ in 0..10 {
+        world.step();
+        flat.step();
+    }
+    assert_eq!(world.states(), flat.states());
+}
+
+#[test]
+fn delay_lines_and_buffers() {
+    let world = |options: &PruneOptions| {
+        World::from_file_with_prune_options(
+            File::open("./schematics/connections.schem").unwrap(),
+            options,
+        )
+    };
+    let delay_lines = world(&PruneOptions {
+        delay_lines: true,
+        ..PruneOptions::default()
+    });
+    assert!(matches!(
+        FlatWorld::new(&delay_lines),
+        Err(CompileError::DelayLine(Some(_)))
+    ));
+    let buffers = world(&PruneOptions {
+        buffers: true,
+        ..PruneOptions::default()
+    });
+    assert!(matches!(
+        FlatWorld::new(&buffers),
+        Err(CompileError::Buffer(Some(_)))
+    ));
+}
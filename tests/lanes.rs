@@ -0,0 +1,137 @@
+mod common;
+
+use common::{schematic, world};
+use redstone_simulator::world::lanes::{LaneError, LaneWorld, LANES};
+use redstone_simulator::world::World;
+
+/// Runs random trigger inputs in every lane, and compares each lane to a `World` run on the same inputs.
+fn check(file: &str, ticks: usize) {
+    let mut lanes = LaneWorld::new(&world(file)).unwrap();
+    let mut worlds: Vec<World> = (0..LANES).map(|_| world(file)).collect();
+    let triggers: Vec<String> = worlds[0].trigger_names().map(String::from).collect();
+
+    let mut inputs = vec![0u64; triggers.len()];
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut random = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    for tick in 0..ticks {
+        for (name, input) in triggers.iter().zip(&mut inputs) {
+            // Every lane keeps its input with a chance of one half, to get pulses of all lengths.
+            let keep = random();
+            *input = (*input & keep) | (random() & !keep);
+            lanes.set_trigger(name, *input);
+            for (lane, w) in worlds.iter_mut().enumerate() {
+                w.set_trigger(name, *input >> lane & 1 == 1);
+            }
+        }
+
+        lanes.step();
+        for (lane, w) in worlds.iter_mut().enumerate() {
+            w.step();
+            for (probe, v) in w.get_probes() {
+                assert_eq!(
+                    lanes.get_probe(probe).unwrap() >> lane & 1 == 1,
+                    v,
+                    "{file}: probe {probe} differs in lane {lane} at tick {tick}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn matches_world() {
+    for file in [
+        "repeater.schem",
+        "torch.schem",
+        "glass.schem",
+        "redstone_split.schem",
+        "stress_srepeater.schem",
+        "redstone_block.schem",
+        "connections.schem",
+    ] {
+        check(file, 60);
+    }
+}
+
+#[test]
+fn adder() {
+    check("adder.schem", 100);
+}
+
+#[test]
+fn locking() {
+    check("locking.schem", 100);
+}
+
+#[test]
+fn binary_comparators() {
+    for file in ["comparator_inputs.schem", "comparator_strengths.schem"] {
+        check(file, 100);
+    }
+}
+
+#[test]
+fn analog_comparators() {
+    assert!(matches!(
+        LaneWorld::new(&world("comparator.schem")),
+        Err(LaneError::Comparator(Some(_)))
+    ));
+}
+
+/// Two triggers wired together into the block of a torch.
+fn nor() -> World {
+    World::from(schematic(&[
+        &[
+            "minecraft:gold_block",
+            "minecraft:redstone_wire[east=side,north=none,power=0,south=side,west=side]",
+            "minecraft:stone",
+            "minecraft:redstone_wall_torch[facing=east,lit=true]",
+            "minecraft:redstone_wire[east=side,north=none,power=15,south=none,west=side]",
+            "minecraft:diamond_block",
+        ],
+        &[
+            "minecraft:air",
+            "minecraft:redstone_wire[east=none,north=side,power=0,south=side,west=none]",
+            "minecraft:air",
+            "minecraft:air",
+            "minecraft:air",
+            "minecraft:air",
+        ],
+        &[
+            "minecraft:air",
+            "minecraft:gold_block",
+            "minecraft:air",
+            "minecraft:air",
+            "minecraft:air",
+            "minecraft:air",
+        ],
+    ]))
+}
+
+#[test]
+fn exhaustive_nor() {
+    let a = 0xaaaa_aaaa_aaaa_aaaa;
+    let b = 0xcccc_cccc_cccc_cccc;
+
+    let mut lanes = LaneWorld::new(&nor()).unwrap();
+    assert!(lanes.set_trigger("0,0,0", a));
+    assert!(lanes.set_trigger("1,0,2", b));
+    assert!(!lanes.set_trigger("2,0,0", 0));
+    for _ in 0..4 {
+        lanes.step();
+    }
+    assert_eq!(lanes.get_probe("5,0,0"), Some(!(a | b)));
+
+    for lane in 0..4 {
+        let mut world = nor();
+        world.set_trigger("0,0,0", a >> lane & 1 == 1);
+        world.set_trigger("1,0,2", b >> lane & 1 == 1);
+        world.run_until_stable(10).unwrap();
+        assert_eq!(world.get_probe("5,0,0"), Some(!(a | b) >> lane & 1 == 1));
+    }
+}
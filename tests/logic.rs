@@ -0,0 +1,93 @@
+mod common;
+
+use common::{schematic, world};
+use redstone_simulator::world::logic::Expr;
+use redstone_simulator::world::World;
+use std::collections::HashSet;
+
+const AIR: &str = "minecraft:air";
+const WIRE_EW: &str = "minecraft:redstone_wire[east=side,north=none,power=0,south=none,west=side]";
+const WIRE_NS: &str = "minecraft:redstone_wire[east=none,north=side,power=0,south=side,west=none]";
+
+/// Checks that every truth table agrees with the simulation once it is stable.
+fn check_truth_tables(world: impl Fn() -> World) {
+    let logic = world().probe_logic();
+    assert!(!logic.is_empty());
+
+    for probe in logic {
+        let table = probe.truth_table().unwrap();
+        for (values, output) in &table.rows {
+            let mut world = world();
+            for (name, &v) in table.inputs.iter().zip(values) {
+                world.set_trigger(name, v);
+            }
+            world.run_until_stable(1000).unwrap();
+
+            let powered = table
+                .inputs
+                .iter()
+                .zip(values)
+                .filter(|(_, &v)| v)
+                .map(|(name, _)| name.as_str())
+                .collect::<HashSet<_>>();
+            assert_eq!(probe.expr.eval(&powered), *output);
+            assert_eq!(
+                world.get_probe(&probe.probe),
+                Some(*output),
+                "{} with {values:?}",
+                probe.probe
+            );
+        }
+    }
+}
+
+#[test]
+fn schematics() {
+    for file in [
+        "cmp_prune.schem",
+        "comparator.schem",
+        "comparator_inputs.schem",
+        "comparator_strengths.schem",
+        "glass.schem",
+        "redstone_split.schem",
+        "repeater.schem",
+        "torch.schem",
+    ] {
+        check_truth_tables(|| world(file));
+    }
+}
+
+#[test]
+fn nor_gate() {
+    // Two triggers powering the block a torch is attached to.
+    let nor = || {
+        World::from(schematic(&[
+            &[
+                "minecraft:gold_block",
+                WIRE_EW,
+                "minecraft:stone",
+                "minecraft:redstone_wall_torch[facing=east,lit=true]",
+                WIRE_EW,
+                "minecraft:diamond_block",
+            ],
+            &[AIR, AIR, WIRE_NS, AIR, AIR, AIR],
+            &[AIR, AIR, "minecraft:gold_block", AIR, AIR, AIR],
+        ]))
+    };
+
+    let logic = nor().probe_logic();
+    let expr = &logic[0].expr;
+    assert_eq!(expr.to_string(), r#"!("2,0,2" | "0,0,0")"#);
+    assert!(matches!(expr, Expr::Not(_)));
+    check_truth_tables(nor);
+}
+
+#[test]
+fn feedback_loops() {
+    let logic = world("cpu_fib.schem").probe_logic();
+    assert!(logic.iter().any(|p| p.is_sequential()));
+    assert!(logic
+        .iter()
+        .filter(|p| p.is_sequential())
+        .all(|p| p.truth_table().is_none()));
+}
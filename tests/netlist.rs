@@ -0,0 +1,168 @@
+use redstone_simulator::world::netlist::{Netlist, NetlistError};
+use redstone_simulator::world::prune::PruneOptions;
+use redstone_simulator::world::World;
+
+const ADDER_JSON: &str = r#"{
+    "inputs": ["a", "b", "cin"],
+    "outputs": ["s", "cout"],
+    "gates": [
+        {"type": "xor", "inputs": ["a", "b"], "output": "p"},
+        {"type": "xor", "inputs": ["p", "cin"], "output": "s"},
+        {"type": "and", "inputs": ["a", "b"], "output": "g"},
+        {"type": "and", "inputs": ["p", "cin"], "output": "t"},
+        {"type": "or", "inputs": ["g", "t"], "output": "cout"}
+    ]
+}"#;
+
+const ADDER_BLIF: &str = "\
+.model adder
+.inputs a b cin
+.outputs s cout
+# Sum of a, b and cin.
+.names a b cin s
+100 1
+010 1
+001 1
+111 1
+.names a b cin \\
+  cout
+00- 0
+0-0 0
+-00 0
+.end
+";
+
+fn world(netlist: &Netlist) -> World {
+    World::from_netlist(netlist, &PruneOptions::default()).unwrap()
+}
+
+/// Checks that the world adds its inputs once it is stable, for every combination of inputs.
+fn check_adder(netlist: &Netlist) {
+    for i in 0..8 {
+        let (a, b, cin) = (i & 1 != 0, i & 2 != 0, i & 4 != 0);
+        let mut world = world(netlist);
+        world.set_trigger("a", a);
+        world.set_trigger("b", b);
+        world.set_trigger("cin", cin);
+        world.run_until_stable(100).unwrap();
+
+        let sum = a as u8 + b as u8 + cin as u8;
+        assert_eq!(world.get_probe("s"), Some(sum & 1 != 0), "{a} {b} {cin}");
+        assert_eq!(world.get_probe("cout"), Some(sum >= 2), "{a} {b} {cin}");
+    }
+}
+
+#[test]
+fn json_adder() {
+    check_adder(&Netlist::from_json(ADDER_JSON).unwrap());
+}
+
+#[test]
+fn blif_adder() {
+    let netlist = Netlist::from_blif(ADDER_BLIF).unwrap();
+    assert_eq!(netlist.inputs, ["a", "b", "cin"]);
+    assert_eq!(netlist.gates.len(), 2);
+    check_adder(&netlist);
+}
+
+#[test]
+fn gate_delays() {
+    let netlist = Netlist::from_json(
+        r#"{
+            "inputs": ["a"],
+            "outputs": ["slow", "inverted"],
+            "gates": [
+                {"type": "buf", "inputs": ["a"], "output": "slow", "delay": 3},
+                {"type": "not", "inputs": ["a"], "output": "inverted"}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let mut world = world(&netlist);
+    assert_eq!(world.get_probe("inverted"), Some(true));
+    world.set_trigger("a", true);
+    let ticks = world.run_until(|w| w.get_probe("slow") == Some(true), 10);
+    // Probes see their input one tick later.
+    assert_eq!(ticks, Some(4));
+    assert_eq!(world.get_probe("inverted"), Some(false));
+}
+
+#[test]
+fn nor_latch() {
+    let netlist = Netlist::from_json(
+        r#"{
+            "inputs": ["set", "reset"],
+            "outputs": ["q"],
+            "gates": [
+                {"type": "nor", "inputs": ["set", "nq"], "output": "q_bar"},
+                {"type": "nor", "inputs": ["reset", "q_bar"], "output": "q"},
+                {"type": "or", "inputs": ["q"], "output": "nq"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    let mut world = world(&netlist);
+
+    for (input, q) in [("set", true), ("reset", false)] {
+        world.set_trigger(input, true);
+        world.run_until_stable(100).unwrap();
+        world.set_trigger(input, false);
+        world.run_until_stable(100).unwrap();
+        assert_eq!(world.get_probe("q"), Some(q), "after pulsing {input}");
+    }
+}
+
+#[test]
+fn ring_oscillator() {
+    let netlist = Netlist::from_json(
+        r#"{
+            "inputs": ["enable"],
+            "outputs": ["a"],
+            "gates": [
+                {"type": "nor", "inputs": ["enable", "e"], "output": "a"},
+                {"type": "not", "inputs": ["a"], "output": "b"},
+                {"type": "not", "inputs": ["b"], "output": "c"},
+                {"type": "not", "inputs": ["c"], "output": "d"},
+                {"type": "not", "inputs": ["d"], "output": "e"}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let error = World::from_netlist(&netlist, &PruneOptions::default()).err();
+    assert!(
+        matches!(&error, Some(NetlistError::Unsettled(s)) if ["a", "b", "c", "d", "e"].contains(&s.as_str())),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn errors() {
+    let gates = |gates: &str| {
+        let json = format!(r#"{{"inputs": ["a"], "outputs": ["y"], "gates": [{gates}]}}"#);
+        let netlist = Netlist::from_json(&json).unwrap();
+        World::from_netlist(&netlist, &PruneOptions::default()).err()
+    };
+
+    assert!(matches!(
+        gates(r#"{"type": "not", "inputs": ["x"], "output": "y"}"#),
+        Some(NetlistError::Undefined(s)) if s == "x"
+    ));
+    assert!(matches!(
+        gates(r#"{"type": "not", "inputs": ["a"], "output": "a"}"#),
+        Some(NetlistError::MultipleDrivers(s)) if s == "a"
+    ));
+    assert!(matches!(
+        gates(r#"{"type": "xor", "inputs": ["a"], "output": "y"}"#),
+        Some(NetlistError::InvalidGate(s)) if s == "y"
+    ));
+    assert!(matches!(
+        gates(r#"{"type": "or", "inputs": ["a", "y"], "output": "y"}"#),
+        Some(NetlistError::WireLoop(_))
+    ));
+    assert!(matches!(
+        Netlist::from_blif(".inputs a\n.subckt b\n"),
+        Err(NetlistError::Blif(2, _))
+    ));
+}
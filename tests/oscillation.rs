@@ -0,0 +1,89 @@
+mod common;
+
+use common::schematic;
+use redstone_simulator::world::prune::PruneOptions;
+use redstone_simulator::world::World;
+
+const AIR: &str = "minecraft:air";
+const WIRE_NS: &str = "minecraft:redstone_wire[east=none,north=side,power=0,south=side,west=none]";
+
+/// Three repeaters and a torch in a loop, kick-started by a trigger. The two repeaters that feed
+/// the first one have the given delay.
+fn clock(delay: u8, options: &PruneOptions) -> World {
+    let repeater =
+        format!("minecraft:repeater[delay={delay},facing=east,locked=false,powered=false]");
+    World::from_format_with_prune_options(
+        schematic(&[
+            &[
+                "minecraft:gold_block",
+                "minecraft:redstone_wire[east=side,north=none,power=0,south=side,west=side]",
+                "minecraft:repeater[delay=2,facing=west,locked=false,powered=false]",
+                "minecraft:stone",
+                "minecraft:redstone_wall_torch[facing=east,lit=true]",
+            ],
+            &[AIR, WIRE_NS, AIR, AIR, WIRE_NS],
+            &[
+                AIR,
+                "minecraft:redstone_wire[east=side,north=side,power=0,south=none,west=none]",
+                &repeater,
+                &repeater,
+                "minecraft:redstone_wire[east=none,north=side,power=0,south=side,west=side]",
+            ],
+            &[AIR, AIR, AIR, AIR, "minecraft:diamond_block"],
+        ]),
+        options,
+    )
+}
+
+#[test]
+fn detect_clock() {
+    let mut world = clock(2, &PruneOptions::default());
+    assert!(world.detect_oscillation(100).is_none());
+
+    world.step_with_trigger();
+    let oscillation = world.detect_oscillation(100).unwrap();
+    assert_eq!(oscillation.period, 14);
+
+    let mut positions = oscillation.positions;
+    positions.sort();
+    assert_eq!(
+        positions,
+        vec![(2, 0, 0), (2, 0, 2), (3, 0, 2), (4, 0, 0), (4, 0, 3)]
+    );
+}
+
+#[test]
+fn detect_delay_line_clock() {
+    let options = PruneOptions {
+        delay_lines: true,
+        ..PruneOptions::default()
+    };
+    let mut world = clock(2, &options);
+    assert_eq!(world.prune_stats().delay_lines, 1);
+
+    world.step_with_trigger();
+    let oscillation = world.detect_oscillation(100).unwrap();
+    assert_eq!(oscillation.period, 14);
+}
+
+#[test]
+fn stable_world() {
+    let mut world = World::from(std::fs::File::open("./schematics/repeater.schem").unwrap());
+    world.step_with_trigger();
+    assert!(world.detect_oscillation(100).is_none());
+    assert!(world.is_stable());
+}
+
+#[test]
+fn detect_buffer_clock() {
+    let options = PruneOptions {
+        buffers: true,
+        ..PruneOptions::default()
+    };
+    let mut world = clock(1, &options);
+    assert_eq!(world.prune_stats().buffers, 1);
+
+    world.step_with_trigger();
+    let oscillation = world.detect_oscillation(100).unwrap();
+    assert_eq!(oscillation.period, 10);
+}
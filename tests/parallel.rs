@@ -0,0 +1,50 @@
+mod common;
+
+use common::world;
+use redstone_simulator::world::parallel::ParallelWorld;
+
+/// Steps a `World` and a `ParallelWorld` side by side and compares them after every tick.
+fn check(file: &str, ticks: usize, trigger: impl Fn(usize) -> bool) {
+    for threads in [1, 4] {
+        // Split every phase over the threads, even the small ones.
+        common::check(file, ticks, &trigger, |world| {
+            ParallelWorld::new(world, threads)
+                .unwrap()
+                .with_threshold(1)
+        });
+    }
+}
+
+#[test]
+fn matches_world() {
+    for file in [
+        "comparator.schem",
+        "comparator_inputs.schem",
+        "comparator_strengths.schem",
+        "connections.schem",
+        "glass.schem",
+        "locking.schem",
+        "repeater.schem",
+        "torch.schem",
+    ] {
+        check(file, 100, |tick| tick % 17 == 0);
+    }
+}
+
+#[test]
+fn repeater_cycle() {
+    // Repeaters that output to each other are updated in rounds on a single thread.
+    check("stress_repeater.schem", 200, |tick| tick == 0);
+}
+
+#[test]
+fn cpu_fib() {
+    check("cpu_fib.schem", 420, |tick| tick % 42 < 2);
+}
+
+#[test]
+fn set_trigger() {
+    let mut parallel = ParallelWorld::new(&world("repeater.schem"), 2).unwrap();
+    assert!(parallel.set_trigger("0,1,0", true));
+    assert!(!parallel.set_trigger("missing", true));
+}
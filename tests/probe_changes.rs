@@ -0,0 +1,78 @@
+use redstone_simulator::world::World;
+use std::collections::HashMap;
+use std::fs::File;
+
+type Changes = Vec<(usize, String, bool)>;
+
+/// Collects probe changes by comparing all probes after every tick.
+fn changes_by_scanning(world: &mut World, ticks: usize) -> Changes {
+    let mut changes = Vec::new();
+    let mut last: HashMap<String, bool> = world
+        .get_probes()
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+    for _ in 0..ticks {
+        world.step();
+        let mut tick_changes: Changes = world
+            .get_probes()
+            .into_iter()
+            .filter(|(k, v)| last[*k] != *v)
+            .map(|(k, v)| (world.tick_counter(), k.to_string(), v))
+            .collect();
+        tick_changes.sort();
+        for (_, k, v) in &tick_changes {
+            last.insert(k.clone(), *v);
+        }
+        changes.extend(tick_changes);
+    }
+    changes
+}
+
+#[test]
+fn step_n_with_reports_changes() {
+    let mut scanned = World::from(File::open("./schematics/cpu_fib.schem").unwrap());
+    let mut listened = World::from(File::open("./schematics/cpu_fib.schem").unwrap());
+
+    for _ in 0..10 {
+        scanned.step_with_trigger();
+        scanned.step_with_trigger();
+        listened.step_with_trigger();
+        listened.step_with_trigger();
+        // Changes caused by the triggers themselves are not compared.
+        listened.probe_changes();
+
+        let expected = changes_by_scanning(&mut scanned, 40);
+        let mut actual = Vec::new();
+        listened.step_n_with(40, |tick, probe, v| {
+            actual.push((tick, probe.to_string(), v))
+        });
+
+        assert!(!expected.is_empty());
+        assert_eq!(expected, actual);
+    }
+}
+
+#[test]
+fn probe_changes_only_once() {
+    let mut world = World::from(File::open("./schematics/repeater.schem").unwrap());
+    assert!(world.probe_changes().is_empty());
+
+    world.step_with_trigger();
+    world.step_n(1);
+    assert_eq!(
+        world.probe_changes(),
+        vec![("extender", true), ("repeater_1t", true)]
+    );
+    assert!(world.probe_changes().is_empty());
+
+    world.step_n(1);
+    assert_eq!(
+        world.probe_changes(),
+        vec![
+            ("extender", false),
+            ("repeater_1t", false),
+            ("repeater_2t", true)
+        ]
+    );
+}
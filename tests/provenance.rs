@@ -0,0 +1,65 @@
+mod common;
+
+use common::schematic;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use redstone_simulator::world::World;
+use std::collections::HashSet;
+use std::fs::File;
+
+const WIRE_EW: &str = "minecraft:redstone_wire[east=side,north=none,power=0,south=none,west=side]";
+
+#[test]
+fn wire_path() {
+    let world = World::from(schematic(&[&[
+        "minecraft:gold_block",
+        WIRE_EW,
+        WIRE_EW,
+        WIRE_EW,
+        "minecraft:diamond_block",
+    ]]));
+
+    let edges: Vec<_> = world.cblocks.edge_references().collect();
+    assert_eq!(edges.len(), 1);
+    let edge = edges[0];
+    assert_eq!(world.provenance.node(edge.source()), [(0, 0, 0)]);
+    assert_eq!(world.provenance.node(edge.target()), [(4, 0, 0)]);
+    assert_eq!(
+        world.provenance.edge(edge.id()),
+        [(1, 0, 0), (2, 0, 0), (3, 0, 0)]
+    );
+
+    let positions = &world.cblock_positions;
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions[&edge.source()], (0, 0, 0));
+    assert_eq!(positions[&edge.target()], (4, 0, 0));
+}
+
+#[test]
+fn merged_nodes() {
+    let world = World::from(File::open("./schematics/cpu_fib.schem").unwrap());
+
+    assert!(world
+        .cblocks
+        .node_indices()
+        .all(|idx| !world.provenance.node(idx).is_empty()));
+    assert!(world
+        .cblocks
+        .node_indices()
+        .any(|idx| world.provenance.node(idx).len() > 1));
+
+    // Every block is represented by at most one node.
+    let mut seen = HashSet::new();
+    for idx in world.cblocks.node_indices() {
+        for &pos in world.provenance.node(idx) {
+            assert!(seen.insert(pos), "{pos:?} is represented twice");
+        }
+    }
+
+    // Only nodes and edges that are still in the graph are recorded.
+    assert_eq!(world.provenance.nodes.len(), world.cblocks.node_count());
+    assert!(world
+        .provenance
+        .edges
+        .keys()
+        .all(|&idx| world.cblocks.edge_weight(idx).is_some()));
+}
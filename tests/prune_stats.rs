@@ -0,0 +1,61 @@
+use redstone_simulator::world::prune::PruneOptions;
+use redstone_simulator::world::World;
+use std::fs::File;
+
+fn world(file: &str, options: &PruneOptions) -> World {
+    World::from_file_with_prune_options(
+        File::open(format!("./schematics/{file}")).unwrap(),
+        options,
+    )
+}
+
+#[test]
+fn passes_chain() {
+    let world = world("cpu_fib.schem", &PruneOptions::default());
+    let stats = world.prune_stats();
+
+    assert_eq!(stats.passes[0].pass, "redstone");
+    for (a, b) in stats.passes.iter().zip(&stats.passes[1..]) {
+        assert_eq!(a.nodes_after, b.nodes_before);
+        assert_eq!(a.edges_after, b.edges_before);
+    }
+
+    let last = stats.passes.last().unwrap();
+    assert_eq!(last.nodes_after, world.cblocks.node_count());
+    assert_eq!(last.edges_after, world.cblocks.edge_count());
+
+    let groups = stats.passes.iter().find(|p| p.pass == "groups").unwrap();
+    assert_eq!(
+        stats.groups_merged,
+        groups.nodes_before - groups.nodes_after
+    );
+    assert!(stats.groups_merged > 0);
+
+    let identical = stats.passes.iter().find(|p| p.pass == "identical").unwrap();
+    assert_eq!(
+        stats.identical_merged,
+        identical.nodes_before - identical.nodes_after
+    );
+    assert!(stats.identical_merged > 0);
+    assert!(stats.simple_repeaters > 0);
+    assert!(stats.binary_comparators > 0);
+}
+
+#[test]
+fn disabled_passes() {
+    let world = world("cpu_fib.schem", &PruneOptions::none());
+    let stats = world.prune_stats();
+
+    assert_eq!(stats.passes.len(), 1);
+    assert_eq!(stats.groups_merged, 0);
+    assert_eq!(stats.identical_merged, 0);
+    assert_eq!(stats.simple_repeaters, 0);
+    assert_eq!(stats.constants_folded, 0);
+    assert_eq!(stats.binary_comparators, 0);
+}
+
+#[test]
+fn constants_folded() {
+    let world = world("redstone_block.schem", &PruneOptions::default());
+    assert!(world.prune_stats().constants_folded > 0);
+}
@@ -0,0 +1,291 @@
+mod common;
+
+use common::schematic;
+use redstone_simulator::world::prune::PruneOptions;
+use redstone_simulator::world::reference::{differential, ReferenceWorld};
+use redstone_simulator::world::schematic::SchemFormat;
+use redstone_simulator::world::World;
+use std::fs::{self, File};
+
+/// Names of all schematic files in `schematics/`.
+fn schematics() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir("./schematics")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".schem"))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Schematic on which `World::step` never returns once its repeaters are pulsed again while they
+/// are still running, see `stress_repeater_repeated_pulses`.
+const HANGS: &str = "stress_repeater.schem";
+
+/// Names of the schematic files that can be run with random inputs.
+fn random_schematics() -> Vec<String> {
+    schematics().into_iter().filter(|f| f != HANGS).collect()
+}
+
+/// Runs a `World` and a `ReferenceWorld` of the schematic side by side on the given inputs.
+fn check(file: &str, inputs: impl FnOnce(&[String]) -> Vec<Vec<(String, bool)>>) {
+    check_with(file, &PruneOptions::default(), inputs);
+}
+
+fn check_with(
+    file: &str,
+    options: &PruneOptions,
+    inputs: impl FnOnce(&[String]) -> Vec<Vec<(String, bool)>>,
+) {
+    let path = format!("./schematics/{file}");
+    let mut world = World::from_file_with_prune_options(File::open(&path).unwrap(), options);
+    let mut reference = ReferenceWorld::from(File::open(&path).unwrap());
+    let triggers: Vec<String> = world.trigger_names().map(String::from).collect();
+
+    let inputs = inputs(&triggers);
+    if let Some(d) = differential(&mut world, &mut reference, &inputs) {
+        panic!("{file} with {options:?}: {d}");
+    }
+}
+
+/// Random inputs for every tick, where every trigger is pulsed for a single tick at random.
+fn random_pulses(triggers: &[String], ticks: usize, mut seed: u64) -> Vec<Vec<(String, bool)>> {
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let mut powered = vec![false; triggers.len()];
+    (0..ticks)
+        .map(|_| {
+            let mut tick = vec![];
+            for (t, p) in triggers.iter().zip(powered.iter_mut()) {
+                if *p || random() % 16 == 0 {
+                    *p = !*p;
+                    tick.push((t.clone(), *p));
+                }
+            }
+            tick
+        })
+        .collect()
+}
+
+#[test]
+fn single_pulse() {
+    for file in schematics() {
+        check(&file, |triggers| {
+            let mut inputs = vec![vec![]; 200];
+            inputs[0] = triggers.iter().map(|t| (t.clone(), true)).collect();
+            inputs[1] = triggers.iter().map(|t| (t.clone(), false)).collect();
+            inputs
+        });
+    }
+}
+
+#[test]
+fn random_inputs() {
+    for file in random_schematics() {
+        check(&file, |triggers| {
+            random_pulses(triggers, 500, 0x2545_f491_4f6c_dd1d)
+        });
+    }
+}
+
+/// `World::step` never returns on this schematic when its triggers are pulsed again while the
+/// repeaters are still running: with a pulse every 13 ticks, tick 15 does not finish. The
+/// simulator of the baseline hangs in the same way.
+#[test]
+#[ignore = "World::step never returns on stress_repeater.schem"]
+fn stress_repeater_repeated_pulses() {
+    check(HANGS, |triggers| {
+        (0..100)
+            .map(|tick| match tick % 13 {
+                0 => triggers.iter().map(|t| (t.clone(), true)).collect(),
+                1 => triggers.iter().map(|t| (t.clone(), false)).collect(),
+                _ => vec![],
+            })
+            .collect()
+    });
+}
+
+#[test]
+fn prune_options() {
+    let default = PruneOptions::default();
+    let options = [
+        PruneOptions::none(),
+        PruneOptions {
+            duplicate_edges: false,
+            ..default
+        },
+        PruneOptions {
+            untraversable_edges: false,
+            ..default
+        },
+        PruneOptions {
+            groups: false,
+            ..default
+        },
+        PruneOptions {
+            identical: false,
+            ..default
+        },
+        PruneOptions {
+            irrelevant: false,
+            ..default
+        },
+        PruneOptions {
+            delay_lines: true,
+            ..default
+        },
+        PruneOptions {
+            simple_repeaters: false,
+            ..default
+        },
+        PruneOptions {
+            subtractor_edges: false,
+            ..default
+        },
+        PruneOptions {
+            constants: false,
+            ..default
+        },
+        PruneOptions {
+            dead_nodes: false,
+            ..default
+        },
+        PruneOptions {
+            binary_comparators: false,
+            ..default
+        },
+        PruneOptions {
+            buffers: true,
+            ..default
+        },
+    ];
+    for file in random_schematics() {
+        for options in &options {
+            check_with(&file, options, |triggers| {
+                random_pulses(triggers, 100, 0x9e37_79b9_7f4a_7c15)
+            });
+        }
+    }
+}
+
+/// Pulses the trigger of the schematic twice, with every combination of pulse and gap lengths
+/// below `max`, and compares the world built with `options` to a `ReferenceWorld`. Pulses and
+/// gaps shorter than the delays of the blocks are extended and shortened by them.
+fn check_pulses(schematic: impl Fn() -> SchemFormat, options: &PruneOptions, max: usize) {
+    for pulse in 1..max {
+        for gap in 1..max {
+            let mut world = World::from_format_with_prune_options(schematic(), options);
+            let mut reference = ReferenceWorld::from(schematic());
+            let triggers: Vec<String> = world.trigger_names().map(String::from).collect();
+
+            let mut inputs = vec![vec![]; 40];
+            for start in [0, pulse + gap] {
+                inputs[start] = vec![(triggers[0].clone(), true)];
+                inputs[start + pulse] = vec![(triggers[0].clone(), false)];
+            }
+            if let Some(d) = differential(&mut world, &mut reference, &inputs) {
+                panic!("pulse of {pulse} ticks, gap of {gap} ticks: {d}");
+            }
+        }
+    }
+}
+
+#[test]
+fn delay_line() {
+    const WIRE_EW: &str =
+        "minecraft:redstone_wire[east=side,north=none,power=0,south=none,west=side]";
+    let repeater = |delay: u8| {
+        format!("minecraft:repeater[delay={delay},facing=west,locked=false,powered=false]")
+    };
+    let (r1, r3, r2, r4) = (repeater(1), repeater(3), repeater(2), repeater(4));
+    let chain = || {
+        schematic(&[&[
+            "minecraft:gold_block",
+            WIRE_EW,
+            &r1,
+            &r3,
+            &r2,
+            &r4,
+            WIRE_EW,
+            "minecraft:diamond_block",
+        ]])
+    };
+    let options = PruneOptions {
+        delay_lines: true,
+        ..PruneOptions::default()
+    };
+
+    let world = World::from_format_with_prune_options(chain(), &options);
+    assert_eq!(world.prune_stats().delay_lines, 1);
+    check_pulses(chain, &options, 6);
+}
+
+#[test]
+fn buffer() {
+    const WIRE_EW: &str =
+        "minecraft:redstone_wire[east=side,north=none,power=0,south=none,west=side]";
+    const TORCH: &str = "minecraft:redstone_wall_torch[facing=east,lit=true]";
+    const UNLIT_TORCH: &str = "minecraft:redstone_wall_torch[facing=east,lit=false]";
+    let chain = || {
+        schematic(&[&[
+            "minecraft:gold_block",
+            WIRE_EW,
+            "minecraft:stone",
+            TORCH,
+            WIRE_EW,
+            "minecraft:stone",
+            UNLIT_TORCH,
+            "minecraft:repeater[delay=1,facing=west,locked=false,powered=false]",
+            WIRE_EW,
+            "minecraft:diamond_block",
+        ]])
+    };
+    let options = PruneOptions {
+        buffers: true,
+        ..PruneOptions::default()
+    };
+
+    let world = World::from_format_with_prune_options(chain(), &options);
+    assert_eq!(world.prune_stats().buffers, 1);
+    check_pulses(chain, &options, 4);
+}
+
+#[test]
+fn constant_gates() {
+    const AIR: &str = "minecraft:air";
+    const WIRE_EW: &str =
+        "minecraft:redstone_wire[east=side,north=none,power=0,south=none,west=side]";
+    // A repeater powered by a redstone block locks the repeater between the trigger and the probe.
+    let locked = || {
+        schematic(&[
+            &[AIR, AIR, "minecraft:redstone_block", AIR, AIR],
+            &[
+                AIR,
+                AIR,
+                "minecraft:repeater[delay=2,facing=north,locked=false,powered=true]",
+                AIR,
+                AIR,
+            ],
+            &[
+                "minecraft:gold_block",
+                WIRE_EW,
+                "minecraft:repeater[delay=1,facing=west,locked=true,powered=false]",
+                WIRE_EW,
+                "minecraft:diamond_block",
+            ],
+        ])
+    };
+
+    let mut world = World::from(locked());
+    assert_eq!(world.prune_stats().constants_folded, 2);
+    let mut reference = ReferenceWorld::from(locked());
+    let triggers: Vec<String> = world.trigger_names().map(String::from).collect();
+    let inputs = random_pulses(&triggers, 50, 0x2545_f491_4f6c_dd1d);
+    if let Some(d) = differential(&mut world, &mut reference, &inputs) {
+        panic!("{d}");
+    }
+}
@@ -0,0 +1,45 @@
+use redstone_simulator::world::World;
+use std::fs::File;
+
+#[test]
+fn run_until_probe() {
+    let mut world = World::from(File::open("./schematics/repeater.schem").unwrap());
+    world.step_with_trigger();
+    assert_eq!(
+        world.run_until(|w| w.get_probe("repeater_4t").unwrap(), 20),
+        Some(4)
+    );
+    assert_eq!(
+        world.run_until(|w| !w.get_probe("repeater_4t").unwrap(), 20),
+        Some(4)
+    );
+}
+
+#[test]
+fn run_until_probe_timeout() {
+    let mut world = World::from(File::open("./schematics/repeater.schem").unwrap());
+    assert_eq!(
+        world.run_until(|w| w.get_probe("repeater_1t").unwrap(), 10),
+        None
+    );
+}
+
+#[test]
+fn run_until_stable() {
+    let mut world = World::from(File::open("./schematics/repeater.schem").unwrap());
+    assert_eq!(world.run_until_stable(10), Ok(0));
+
+    world.step_with_trigger();
+    assert_eq!(world.run_until_stable(20), Ok(8));
+    assert!(world.is_stable());
+}
+
+#[test]
+fn run_until_stable_cpu() {
+    let mut world = World::from(File::open("./schematics/cpu_fib.schem").unwrap());
+    world.step_with_trigger();
+    world.step_with_trigger();
+    let ticks = world.run_until_stable(1000).unwrap();
+    assert!(ticks > 0);
+    assert_eq!(world.run_until_stable(1000), Ok(0));
+}
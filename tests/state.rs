@@ -0,0 +1,124 @@
+use redstone_simulator::world::prune::PruneOptions;
+use redstone_simulator::world::state::StateError;
+use redstone_simulator::world::World;
+use std::collections::HashMap;
+use std::fs::File;
+
+fn run_cpu(world: &mut World, cycles: usize) -> Vec<HashMap<String, bool>> {
+    (0..cycles)
+        .map(|_| {
+            world.step_with_trigger();
+            world.step_with_trigger();
+            for _ in 0..40 {
+                world.step();
+            }
+            world
+                .get_probes()
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn save_and_resume() {
+    let mut world = World::from(File::open("./schematics/cpu_fib.schem").unwrap());
+    run_cpu(&mut world, 5);
+    // Save in the middle of a cycle, so the update queues are not empty.
+    world.step_with_trigger();
+
+    let mut state = Vec::new();
+    world.save_state(&mut state).unwrap();
+
+    let mut resumed = World::from(File::open("./schematics/cpu_fib.schem").unwrap());
+    resumed.load_state(state.as_slice()).unwrap();
+
+    let mut resumed_state = Vec::new();
+    resumed.save_state(&mut resumed_state).unwrap();
+    assert_eq!(state, resumed_state);
+
+    assert_eq!(run_cpu(&mut world, 10), run_cpu(&mut resumed, 10));
+}
+
+#[test]
+fn save_and_resume_delay_lines() {
+    let options = PruneOptions {
+        delay_lines: true,
+        ..PruneOptions::default()
+    };
+    let cpu = || {
+        World::from_file_with_prune_options(
+            File::open("./schematics/cpu_fib.schem").unwrap(),
+            &options,
+        )
+    };
+    let mut world = cpu();
+    run_cpu(&mut world, 3);
+    // Save while pulses are travelling through the delay lines.
+    world.step_with_trigger();
+    world.step();
+
+    let mut state = Vec::new();
+    world.save_state(&mut state).unwrap();
+    let mut resumed = cpu();
+    resumed.load_state(state.as_slice()).unwrap();
+
+    assert_eq!(run_cpu(&mut world, 5), run_cpu(&mut resumed, 5));
+}
+
+#[test]
+fn refuse_other_schematic() {
+    let world = World::from(File::open("./schematics/cpu_fib.schem").unwrap());
+    let mut state = Vec::new();
+    world.save_state(&mut state).unwrap();
+
+    let mut other = World::from(File::open("./schematics/adder.schem").unwrap());
+    assert!(matches!(
+        other.load_state(state.as_slice()),
+        Err(StateError::Mismatch)
+    ));
+}
+
+#[test]
+fn failed_load_leaves_world_intact() {
+    let options = PruneOptions {
+        delay_lines: true,
+        ..PruneOptions::default()
+    };
+    let cpu = || {
+        World::from_file_with_prune_options(
+            File::open("./schematics/cpu_fib.schem").unwrap(),
+            &options,
+        )
+    };
+    let mut world = cpu();
+    run_cpu(&mut world, 3);
+    let mut state = Vec::new();
+    world.save_state(&mut state).unwrap();
+
+    // Drop a stage of the last delay line, so every node before it does accept its state.
+    let mut json: serde_json::Value = serde_json::from_slice(&state).unwrap();
+    let stages = json["nodes"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .rev()
+        .find_map(|n| n.get_mut("DelayLine"))
+        .unwrap()["stages"]
+        .as_array_mut()
+        .unwrap();
+    stages.pop();
+    let corrupted = serde_json::to_vec(&json).unwrap();
+
+    let mut other = cpu();
+    let mut before = Vec::new();
+    other.save_state(&mut before).unwrap();
+    assert!(matches!(
+        other.load_state(corrupted.as_slice()),
+        Err(StateError::Mismatch)
+    ));
+    let mut after = Vec::new();
+    other.save_state(&mut after).unwrap();
+    assert_eq!(before, after);
+}
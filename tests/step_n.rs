@@ -0,0 +1,53 @@
+use redstone_simulator::world::World;
+use std::fs::File;
+
+fn state(world: &World) -> Vec<u8> {
+    let mut state = Vec::new();
+    world.save_state(&mut state).unwrap();
+    state
+}
+
+/// Checks that `step_n` leaves the world in exactly the same state as repeatedly calling `step`.
+fn check(file: &str, cycles: usize, triggers: usize, steps: usize) {
+    let mut stepped = World::from(File::open(format!("./schematics/{file}.schem")).unwrap());
+    let mut skipped = World::from(File::open(format!("./schematics/{file}.schem")).unwrap());
+
+    for _ in 0..cycles {
+        for _ in 0..triggers {
+            stepped.step_with_trigger();
+            skipped.step_with_trigger();
+        }
+        for n in 1..=steps {
+            for _ in 0..n {
+                stepped.step();
+            }
+            skipped.step_n(n);
+            assert_eq!(state(&stepped), state(&skipped));
+        }
+    }
+}
+
+#[test]
+fn step_n_repeater() {
+    check("repeater", 3, 1, 6);
+}
+
+#[test]
+fn step_n_locking() {
+    check("locking", 3, 1, 6);
+}
+
+#[test]
+fn step_n_stress_repeater() {
+    check("stress_repeater", 1, 1, 12);
+}
+
+#[test]
+fn step_n_comparator() {
+    check("comparator", 2, 1, 4);
+}
+
+#[test]
+fn step_n_cpu() {
+    check("cpu_fib", 5, 2, 8);
+}
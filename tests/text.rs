@@ -0,0 +1,75 @@
+use redstone_simulator::test;
+use redstone_simulator::test_macro::{F, T};
+use redstone_simulator::world::text::{parse, TextSchematicError};
+use redstone_simulator::world::World;
+
+test!("text", text_torch; T, T, F, T);
+test!("text", text_repeater; F, F, F, T, T, F);
+
+#[test]
+fn names_and_positions() {
+    let world = redstone_simulator::test_macro::load("text");
+    let mut triggers = world.trigger_names().collect::<Vec<_>>();
+    triggers.sort();
+    assert_eq!(triggers, ["text_repeater_in", "text_torch_in"]);
+
+    let timing = world.timing();
+    let path = timing
+        .paths
+        .iter()
+        .find(|p| p.probe == "text_repeater")
+        .unwrap();
+    assert_eq!(path.critical_path.first(), Some(&(0, 0, 2)));
+    assert_eq!(path.critical_path.last(), Some(&(4, 0, 2)));
+}
+
+#[test]
+fn layers() {
+    let builder = parse(
+        "legend W minecraft:stone\n\
+         layer -1\n\
+         W.W\n\
+         \n\
+         layer 1\n\
+         .\n\
+         W\n",
+    )
+    .unwrap();
+    let schematic = builder.schematic();
+    assert_eq!(schematic.offset, [0, -1, 0]);
+    assert_eq!(
+        (schematic.width, schematic.height, schematic.length),
+        (3, 3, 2)
+    );
+}
+
+#[test]
+fn errors() {
+    let line = |s: &str| {
+        World::from_text(s)
+            .err()
+            .map(|e: TextSchematicError| e.line)
+    };
+
+    assert_eq!(line("layer 0\nx"), Some(2));
+    assert_eq!(line("# comment\nlegend ab minecraft:stone"), Some(2));
+    assert_eq!(line("legend . minecraft:stone"), Some(1));
+    assert_eq!(line("name 0 0 probe"), Some(1));
+    assert_eq!(line("signal 0 0 0 16"), Some(1));
+    assert_eq!(line("layer up"), Some(1));
+    assert_eq!(line("block 0 0 0"), Some(1));
+    assert_eq!(
+        line("legend S minecraft:stone\nlegend s minecraft:stnoe"),
+        Some(2)
+    );
+    assert_eq!(line("legend r minecraft:repeater[facing=west]"), Some(1));
+    assert_eq!(
+        line("legend r minecraft:repeater[delay=5,facing=west,locked=false,powered=false]"),
+        Some(1)
+    );
+    assert_eq!(line("legend - minecraft:redstone_wire[power]"), Some(1));
+    assert_eq!(
+        line("legend c minecraft:comparator[facing=up,mode=compare,powered=false]"),
+        Some(1)
+    );
+}
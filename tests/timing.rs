@@ -0,0 +1,47 @@
+use redstone_simulator::world::World;
+use std::collections::HashSet;
+use std::fs::File;
+
+fn world(file: &str) -> World {
+    World::from(File::open(format!("./schematics/{file}")).unwrap())
+}
+
+#[test]
+fn repeater_delays() {
+    let timing = world("repeater.schem").timing();
+    assert!(!timing.paths.is_empty());
+
+    for path in &timing.paths {
+        assert_eq!(path.max, Some(path.min));
+
+        // The delay is the number of ticks until the probe changes.
+        let mut world = world("repeater.schem");
+        world.set_trigger(&path.trigger, true);
+        let ticks = world.run_until(|w| w.get_probe(&path.probe) == Some(true), 100);
+        assert_eq!(ticks, Some(path.min), "{} -> {}", path.trigger, path.probe);
+    }
+}
+
+#[test]
+fn critical_path() {
+    let timing = world("repeater.schem").timing();
+    let critical = timing.critical().unwrap();
+    assert_eq!(critical.probe, "repeater_4t");
+    assert_eq!(critical.max, Some(5));
+
+    // The path runs through every block between the trigger and the probe once.
+    let path = &critical.critical_path;
+    assert!(path.len() > 2);
+    assert_eq!(path.iter().collect::<HashSet<_>>().len(), path.len());
+}
+
+#[test]
+fn feedback_loops() {
+    let timing = world("cpu_fib.schem").timing();
+    assert!(!timing.paths.is_empty());
+    for path in &timing.paths {
+        assert!(path.max.is_none_or(|max| max >= path.min));
+        assert_eq!(path.max.is_none(), path.critical_path.is_empty());
+    }
+    assert!(timing.paths.iter().any(|p| p.max.is_none()));
+}
@@ -0,0 +1,40 @@
+use redstone_simulator::test_macro::{load, Trace, F, T};
+use redstone_simulator::trace;
+use std::panic;
+
+trace!("text", inverter_and_repeater;
+    [text_torch_in, "text_repeater_in"] => [text_torch, text_repeater];
+    [T, T] => [T, F],
+    [_, _] => [T, F],
+    [F, _] => [F, F],
+    [_, F] => [F, T],
+    [_, _] => [T, T],
+    [_, _] => [T, T],
+    [_, _] => [T, F],
+);
+
+trace!("text", no_inputs; [] => [text_torch, text_repeater];
+    [] => [T, F],
+    [] => [T, F],
+);
+
+#[test]
+fn mismatch() {
+    let trace = Trace::new(&["text_torch_in"], &["text_torch", "text_repeater"])
+        .tick(&[Some(true)], &[true, false])
+        .tick(&[None], &[true, false])
+        .tick(&[None], &[true, false]);
+
+    let result = panic::catch_unwind(|| trace.check(&mut load("text")));
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert_eq!(
+        message,
+        "Probes differ from the expected trace:\n\
+         tick                   012\n\
+         text_torch_in          1..\n\
+         text_torch expected    111\n\
+         \x20               actual 110\n\
+         \x20                        ^\n\
+         text_repeater expected 000"
+    );
+}
@@ -0,0 +1,191 @@
+use redstone_simulator::world::verilog::{identifier, netlist, PRIMITIVES};
+use redstone_simulator::world::World;
+use std::fmt::Write;
+use std::fs::File;
+use std::process::Command;
+
+fn world(file: &str) -> World {
+    World::from(File::open(format!("./schematics/{file}")).unwrap())
+}
+
+/// Returns the ports of the module declared by the netlist.
+fn ports(netlist: &str) -> Vec<&str> {
+    let start = netlist.find(" (\n").unwrap() + 3;
+    let end = start + netlist[start..].find("\n);").unwrap();
+    netlist[start..end]
+        .split(",\n")
+        .map(|port| port.trim())
+        .collect()
+}
+
+#[test]
+fn adder_ports() {
+    let netlist = netlist(&world("adder.schem"), "adder").unwrap();
+    assert!(netlist.contains("module adder ("));
+
+    let mut expected = vec!["input wire clk".to_string()];
+    expected.extend((0..8).map(|i| format!("output wire \\{i}")));
+    assert_eq!(ports(&netlist), expected);
+    assert!(netlist.trim_end().ends_with("endmodule"));
+}
+
+#[test]
+fn trigger_ports() {
+    let world = world("repeater.schem");
+    let netlist = netlist(&world, "repeater").unwrap();
+    let ports = ports(&netlist);
+
+    for trigger in world.trigger_names() {
+        assert!(ports.contains(&format!("input wire \\{trigger}").as_str()));
+    }
+    for probe in world.get_probes().keys() {
+        assert!(ports.contains(&format!("output wire {probe}").as_str()));
+    }
+}
+
+#[test]
+fn primitives() {
+    for file in ["locking.schem", "comparator.schem", "torch.schem"] {
+        let netlist = netlist(&world(file), "top").unwrap();
+        let used = netlist
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("rs_"))
+            .map(|l| format!("module rs_{}", l.split_whitespace().next().unwrap()))
+            .collect::<Vec<_>>();
+        assert!(!used.is_empty(), "{file}");
+        for module in used {
+            assert!(PRIMITIVES.contains(&module), "{module}");
+        }
+    }
+}
+
+/// Simulates the netlist of the schematic in Icarus Verilog, pulsing every trigger at the given
+/// ticks, and compares the probes after every tick with a `World`.
+fn check_trace(file: &str, ticks: usize, pulse: impl Fn(usize) -> bool) {
+    let mut world = world(file);
+    let netlist = netlist(&world, "top").unwrap();
+    let ports = ports(&netlist);
+    let triggers = ports
+        .iter()
+        .filter(|p| p.starts_with("input wire ") && **p != "input wire clk")
+        .count();
+    // Names of the probes in the order of the outputs of the module.
+    let probes: Vec<String> = ports
+        .iter()
+        .filter_map(|p| p.strip_prefix("output wire "))
+        .map(|o| {
+            world
+                .get_probes()
+                .into_keys()
+                .find(|p| identifier(p).trim_end() == o)
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert!(triggers > 0 && !probes.is_empty(), "{file}");
+
+    // Every tick sets the triggers, prints the probes and then raises the clock. The probes are
+    // printed before the clock edge, since a probe reacts one tick after the block feeding it.
+    let mut tb = String::new();
+    writeln!(tb, "module tb;").unwrap();
+    writeln!(tb, "    reg clk = 0;").unwrap();
+    writeln!(tb, "    reg [{}:0] t = 0;", triggers - 1).unwrap();
+    writeln!(tb, "    wire [{}:0] p;", probes.len() - 1).unwrap();
+    let connections = (0..triggers)
+        .map(|i| format!("t[{i}]"))
+        .chain((0..probes.len()).map(|i| format!("p[{i}]")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    writeln!(tb, "    top dut (clk, {connections});").unwrap();
+    writeln!(tb, "    initial begin").unwrap();
+    for tick in 0..ticks {
+        let value = if pulse(tick) { 1 } else { 0 };
+        writeln!(tb, "        t = {{{triggers}{{1'b{value}}}}};").unwrap();
+        writeln!(tb, "        #1 $display(\"%b\", p);").unwrap();
+        writeln!(tb, "        clk = 1;").unwrap();
+        writeln!(tb, "        #1 clk = 0;").unwrap();
+    }
+    writeln!(tb, "        $finish;").unwrap();
+    writeln!(tb, "    end").unwrap();
+    writeln!(tb, "endmodule").unwrap();
+
+    let dir = std::env::temp_dir().join(format!(
+        "redstone-verilog-{}-{}",
+        file.trim_end_matches(".schem"),
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tb.v"), tb).unwrap();
+    std::fs::write(dir.join("top.v"), &netlist).unwrap();
+    std::fs::write(dir.join("redstone_primitives.v"), PRIMITIVES).unwrap();
+    let status = Command::new("iverilog")
+        .current_dir(&dir)
+        .args([
+            "-g2012",
+            "-o",
+            "sim",
+            "tb.v",
+            "top.v",
+            "redstone_primitives.v",
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success(), "{file}: iverilog failed");
+    let output = Command::new("vvp")
+        .current_dir(&dir)
+        .args(["-n", "sim"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{file}: vvp failed");
+
+    // `$display` prints the highest bit first.
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let trace = stdout
+        .lines()
+        .filter(|l| l.len() == probes.len() && l.chars().all(|c| c == '0' || c == '1'))
+        .map(|l| l.chars().rev().collect::<String>());
+    let mut count = 0;
+    for (tick, line) in trace.enumerate() {
+        if pulse(tick) {
+            world.step_with_trigger();
+        } else {
+            world.step();
+        }
+        let expected: String = probes
+            .iter()
+            .map(|p| {
+                if world.get_probe(p).unwrap() {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect();
+        assert_eq!(
+            line, expected,
+            "{file}: probes {probes:?} differ at tick {tick}"
+        );
+        count += 1;
+    }
+    assert_eq!(count, ticks, "{file}");
+}
+
+/// Needs `iverilog` and `vvp` on the path, run with `cargo test -- --ignored`.
+#[test]
+#[ignore = "requires Icarus Verilog"]
+fn iverilog_trace() {
+    Command::new("iverilog")
+        .arg("-V")
+        .output()
+        .expect("iverilog is not installed");
+
+    for file in [
+        "repeater.schem",
+        "locking.schem",
+        "torch.schem",
+        "comparator_inputs.schem",
+        "comparator_strengths.schem",
+    ] {
+        check_trace(file, 100, |tick| tick % 17 < 2);
+    }
+}